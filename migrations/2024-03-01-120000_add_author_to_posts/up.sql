@@ -0,0 +1,4 @@
+ALTER TABLE posts
+    ADD COLUMN author_id uuid REFERENCES users (id) ON DELETE SET NULL;
+
+CREATE INDEX posts_author_id_idx ON posts (author_id);
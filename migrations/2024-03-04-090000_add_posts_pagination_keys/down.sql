@@ -0,0 +1,5 @@
+DROP INDEX posts_title_id_idx;
+DROP INDEX posts_created_at_id_idx;
+
+ALTER TABLE posts
+    DROP COLUMN created_at
@@ -0,0 +1,6 @@
+ALTER TABLE posts
+    ADD COLUMN created_at timestamptz NOT NULL DEFAULT now();
+
+-- Keyset pagination walks these indexes in both directions
+CREATE INDEX posts_created_at_id_idx ON posts (created_at, id);
+CREATE INDEX posts_title_id_idx ON posts (title, id);
@@ -0,0 +1,5 @@
+DROP TRIGGER IF EXISTS set_updated_at ON posts;
+
+ALTER TABLE posts
+    DROP COLUMN published_at,
+    DROP COLUMN updated_at
@@ -0,0 +1,10 @@
+ALTER TABLE posts
+    ADD COLUMN updated_at   timestamptz NOT NULL DEFAULT now(),
+    ADD COLUMN published_at timestamptz;
+
+-- Posts that are already live have no better publication time than their creation
+UPDATE posts
+SET published_at = created_at
+WHERE published;
+
+SELECT diesel_manage_updated_at('posts');
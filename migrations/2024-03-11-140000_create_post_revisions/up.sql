@@ -0,0 +1,17 @@
+CREATE TABLE IF NOT EXISTS post_revisions
+(
+    id            uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
+    post_id       uuid        NOT NULL REFERENCES posts (id) ON DELETE CASCADE,
+    revision      INTEGER     NOT NULL,
+    title         VARCHAR     NOT NULL,
+    body          TEXT        NOT NULL,
+    editor_id     uuid REFERENCES users (id) ON DELETE SET NULL,
+    restored_from INTEGER,
+    created_at    timestamptz NOT NULL DEFAULT now(),
+    UNIQUE (post_id, revision)
+);
+
+-- Existing posts start their history with what they look like today
+INSERT INTO post_revisions (post_id, revision, title, body, editor_id, created_at)
+SELECT id, 1, title, body, author_id, updated_at
+FROM posts;
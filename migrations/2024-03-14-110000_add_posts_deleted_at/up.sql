@@ -0,0 +1,5 @@
+ALTER TABLE posts
+    ADD COLUMN deleted_at timestamptz;
+
+-- Only trashed posts are indexed, which is what the trash listing and the purge look for
+CREATE INDEX posts_deleted_at_idx ON posts (deleted_at) WHERE deleted_at IS NOT NULL;
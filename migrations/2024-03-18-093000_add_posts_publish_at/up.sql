@@ -0,0 +1,5 @@
+ALTER TABLE posts
+    ADD COLUMN publish_at timestamptz;
+
+-- The scheduler only ever looks at drafts waiting to go live
+CREATE INDEX posts_publish_at_idx ON posts (publish_at) WHERE publish_at IS NOT NULL AND NOT published;
@@ -0,0 +1,4 @@
+DROP TABLE post_slug_history;
+
+ALTER TABLE posts
+    DROP COLUMN slug
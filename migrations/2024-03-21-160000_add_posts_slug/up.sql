@@ -0,0 +1,21 @@
+ALTER TABLE posts
+    ADD COLUMN slug VARCHAR;
+
+-- Existing posts get an ASCII slug of their title, made unique with the start of their id
+UPDATE posts
+SET slug = coalesce(nullif(trim(BOTH '-' FROM lower(regexp_replace(title, '[^a-zA-Z0-9]+', '-', 'g'))), ''), 'post')
+               || '-' || left(id::text, 8);
+
+ALTER TABLE posts
+    ALTER COLUMN slug SET NOT NULL,
+    ADD CONSTRAINT posts_slug_key UNIQUE (slug);
+
+-- Slugs a post used to have, so that old links can be redirected to the current one
+CREATE TABLE IF NOT EXISTS post_slug_history
+(
+    slug       VARCHAR PRIMARY KEY,
+    post_id    uuid        NOT NULL REFERENCES posts (id) ON DELETE CASCADE,
+    created_at timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE INDEX post_slug_history_post_id_idx ON post_slug_history (post_id);
@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS tags
+(
+    id         uuid PRIMARY KEY DEFAULT uuid_generate_v4(),
+    name       VARCHAR     NOT NULL UNIQUE,
+    created_at timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS post_tags
+(
+    post_id uuid NOT NULL REFERENCES posts (id) ON DELETE CASCADE,
+    tag_id  uuid NOT NULL REFERENCES tags (id) ON DELETE CASCADE,
+    PRIMARY KEY (post_id, tag_id)
+);
+
+CREATE INDEX post_tags_tag_id_idx ON post_tags (tag_id);
@@ -0,0 +1,19 @@
+CREATE TABLE IF NOT EXISTS comments
+(
+    id         uuid PRIMARY KEY,
+    post_id    uuid        NOT NULL REFERENCES posts (id) ON DELETE CASCADE,
+    author_id  uuid REFERENCES users (id) ON DELETE SET NULL,
+    parent_id  uuid REFERENCES comments (id) ON DELETE CASCADE,
+    -- The top-level comment of the thread, which is the comment itself for top-level comments
+    root_id    uuid        NOT NULL REFERENCES comments (id) ON DELETE CASCADE,
+    body       TEXT        NOT NULL,
+    created_at timestamptz NOT NULL DEFAULT now(),
+    updated_at timestamptz NOT NULL DEFAULT now(),
+    deleted_at timestamptz
+);
+
+CREATE INDEX comments_post_id_root_idx ON comments (post_id, created_at, id) WHERE parent_id IS NULL;
+CREATE INDEX comments_root_id_idx ON comments (root_id);
+CREATE INDEX comments_parent_id_idx ON comments (parent_id);
+
+SELECT diesel_manage_updated_at('comments');
@@ -0,0 +1,2 @@
+ALTER TABLE user_sessions
+    DROP COLUMN oauth_access_token
\ No newline at end of file
@@ -0,0 +1,3 @@
+-- Kept so the provider grant can be revoked when the session ends
+ALTER TABLE user_sessions
+    ADD COLUMN oauth_access_token TEXT;
@@ -0,0 +1,7 @@
+DROP INDEX user_sessions_user_id_idx;
+
+ALTER TABLE user_sessions
+    DROP CONSTRAINT user_sessions_user_id_fkey,
+    DROP COLUMN ip_address,
+    DROP COLUMN user_agent,
+    DROP COLUMN last_seen_at
\ No newline at end of file
@@ -0,0 +1,18 @@
+-- Sessions of users that no longer exist can't be used anyway
+DELETE
+FROM user_sessions
+WHERE user_id NOT IN (SELECT id FROM users);
+
+ALTER TABLE user_sessions
+    ADD CONSTRAINT user_sessions_user_id_fkey FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
+    ADD COLUMN ip_address   TEXT,
+    ADD COLUMN user_agent   TEXT,
+    ADD COLUMN last_seen_at BIGINT;
+
+UPDATE user_sessions
+SET last_seen_at = created_at;
+
+ALTER TABLE user_sessions
+    ALTER COLUMN last_seen_at SET NOT NULL;
+
+CREATE INDEX user_sessions_user_id_idx ON user_sessions (user_id);
@@ -0,0 +1,3 @@
+ALTER TABLE user_sessions
+    DROP COLUMN previous_session_token_p2,
+    DROP COLUMN rotated_at
\ No newline at end of file
@@ -0,0 +1,5 @@
+-- The secret a session had before its last rotation stays valid for a short while, so requests
+-- already in flight with the old cookie don't log the user out
+ALTER TABLE user_sessions
+    ADD COLUMN previous_session_token_p2 TEXT,
+    ADD COLUMN rotated_at                BIGINT;
@@ -0,0 +1,8 @@
+-- Digests can't be turned back into secrets, so every session has to go
+DELETE
+FROM user_sessions;
+
+ALTER TABLE user_sessions
+    RENAME COLUMN previous_session_token_p2_digest TO previous_session_token_p2;
+ALTER TABLE user_sessions
+    RENAME COLUMN session_token_p2_digest TO session_token_p2
@@ -0,0 +1,10 @@
+-- Only SHA-256 digests of the secret half of session tokens are kept. Existing secrets are hashed
+-- in place, so nobody gets logged out.
+ALTER TABLE user_sessions
+    RENAME COLUMN session_token_p2 TO session_token_p2_digest;
+ALTER TABLE user_sessions
+    RENAME COLUMN previous_session_token_p2 TO previous_session_token_p2_digest;
+
+UPDATE user_sessions
+SET session_token_p2_digest          = encode(sha256(convert_to(session_token_p2_digest, 'UTF8')), 'hex'),
+    previous_session_token_p2_digest = encode(sha256(convert_to(previous_session_token_p2_digest, 'UTF8')), 'hex');
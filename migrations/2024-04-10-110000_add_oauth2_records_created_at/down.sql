@@ -0,0 +1,5 @@
+DROP INDEX user_sessions_expires_at_idx;
+DROP INDEX oauth2_records_created_at_idx;
+
+ALTER TABLE oauth2_records
+    DROP COLUMN created_at
\ No newline at end of file
@@ -0,0 +1,6 @@
+-- Abandoned logins leave their records behind; their age tells when they can go
+ALTER TABLE oauth2_records
+    ADD COLUMN created_at timestamptz NOT NULL DEFAULT now();
+
+CREATE INDEX oauth2_records_created_at_idx ON oauth2_records (created_at);
+CREATE INDEX user_sessions_expires_at_idx ON user_sessions (expires_at);
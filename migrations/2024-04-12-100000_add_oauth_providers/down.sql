@@ -0,0 +1,5 @@
+ALTER TABLE user_sessions
+    DROP COLUMN oauth_provider;
+
+ALTER TABLE oauth2_records
+    DROP COLUMN provider
\ No newline at end of file
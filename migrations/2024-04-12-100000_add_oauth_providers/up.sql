@@ -0,0 +1,8 @@
+-- Logins can go through several providers now; everything before went through Google
+ALTER TABLE oauth2_records
+    ADD COLUMN provider VARCHAR(64) NOT NULL DEFAULT 'google';
+ALTER TABLE oauth2_records
+    ALTER COLUMN provider DROP DEFAULT;
+
+ALTER TABLE user_sessions
+    ADD COLUMN oauth_provider VARCHAR(64);
@@ -0,0 +1,4 @@
+ALTER TABLE oauth2_records
+    DROP COLUMN link_user_id;
+
+DROP TABLE user_identities
\ No newline at end of file
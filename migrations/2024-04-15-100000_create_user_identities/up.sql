@@ -0,0 +1,19 @@
+-- Accounts at identity providers, each belonging to one user. Users are found through these
+-- rather than by email address, which providers may share or change.
+CREATE TABLE IF NOT EXISTS user_identities
+(
+    id         uuid PRIMARY KEY     DEFAULT uuid_generate_v4(),
+    user_id    uuid        NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    provider   VARCHAR(64) NOT NULL,
+    subject    TEXT        NOT NULL,
+    -- The address the provider reported last, for display only
+    email      TEXT        NOT NULL,
+    created_at timestamptz NOT NULL DEFAULT now(),
+    UNIQUE (provider, subject),
+    -- One account per provider and user
+    UNIQUE (user_id, provider)
+);
+
+-- A login started by a logged-in user links the identity to them instead of logging in
+ALTER TABLE oauth2_records
+    ADD COLUMN link_user_id uuid REFERENCES users (id) ON DELETE CASCADE;
@@ -0,0 +1,17 @@
+-- Long-lived tokens for scripts and CI, sent as `Authorization: Bearer <token>`. Like sessions,
+-- only a digest of the token is kept.
+CREATE TABLE IF NOT EXISTS personal_access_tokens
+(
+    id           uuid PRIMARY KEY      DEFAULT uuid_generate_v4(),
+    user_id      uuid         NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    name         VARCHAR(100) NOT NULL,
+    scopes       TEXT[]       NOT NULL,
+    -- The first characters of the token, for telling tokens apart
+    token_prefix VARCHAR(16)  NOT NULL,
+    token_digest TEXT         NOT NULL UNIQUE,
+    created_at   timestamptz  NOT NULL DEFAULT now(),
+    expires_at   timestamptz  NOT NULL,
+    last_used_at timestamptz
+);
+
+CREATE INDEX personal_access_tokens_user_id_idx ON personal_access_tokens (user_id);
@@ -0,0 +1,9 @@
+-- What a user may do. Everyone who could write posts so far keeps doing so, new users start out
+-- as readers; the first admin has to be promoted by hand:
+--   UPDATE users SET role = 'admin' WHERE email = '...';
+ALTER TABLE users
+    ADD COLUMN role VARCHAR(16) NOT NULL DEFAULT 'editor'
+        CHECK (role IN ('admin', 'editor', 'reader'));
+
+ALTER TABLE users
+    ALTER COLUMN role SET DEFAULT 'reader';
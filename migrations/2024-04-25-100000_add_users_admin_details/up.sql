@@ -0,0 +1,4 @@
+-- Suspended users keep their data but can't use the site until an admin lets them back in
+ALTER TABLE users
+    ADD COLUMN created_at   timestamptz NOT NULL DEFAULT now(),
+    ADD COLUMN suspended_at timestamptz;
@@ -1,22 +1,80 @@
 use dotenvy::dotenv;
 use tokio::sync::OnceCell;
 use std::env;
+use std::num::NonZeroU64;
+use std::time::Duration;
 
 #[derive(Debug)]
 struct DatabaseConfig {
     url: String,
+    pool_timeout_secs: u64,
 }
 
 #[derive(Debug)]
 struct ServerConfig {
     host: String,
     port: u16,
+    trust_forwarded_for: bool,
+}
+
+#[derive(Debug)]
+struct PostsConfig {
+    trash_retention_days: i64,
+    trash_purge_interval_secs: u64,
+    publish_scheduler_interval_secs: u64,
+    title_max_chars: usize,
+    body_max_bytes: usize,
+}
+
+// The kind of a provider decides the defaults for its endpoints and how its userinfo is read
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OAuthProviderKind {
+    Google,
+    GitHub,
+    GitLab,
+    // Any OpenID Connect provider, its endpoints found through the issuer's discovery document
+    Oidc,
+}
+
+// An OAuth provider users can log in with. Endpoints left out are filled in from the defaults of
+// the kind or, for OpenID Connect, from discovery.
+#[derive(Debug)]
+pub struct OAuthProviderConfig {
+    pub name: String,
+    pub kind: OAuthProviderKind,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    pub issuer_url: Option<String>,
+    pub auth_url: Option<String>,
+    pub token_url: Option<String>,
+    pub userinfo_url: Option<String>,
+    pub emails_url: Option<String>,
+    pub revocation_url: Option<String>,
+    pub scopes: Vec<String>,
+    // Set up through Google's old variables, and so registered with the old redirect URI
+    pub legacy_redirect: bool,
+}
+
+#[derive(Debug)]
+struct AuthConfig {
+    oauth_providers: Vec<OAuthProviderConfig>,
+    session_idle_timeout_secs: i64,
+    session_max_lifetime_secs: i64,
+    revoke_on_logout: bool,
+    revocation_stub: bool,
+    oauth_state_ttl_secs: i64,
+    janitor_interval_secs: u64,
+    token_default_lifetime_days: i64,
+    token_max_lifetime_days: i64,
+    return_url_allowed_origins: Vec<String>,
 }
 
 #[derive(Debug)]
 pub struct Config {
     server: ServerConfig,
     db: DatabaseConfig,
+    posts: PostsConfig,
+    auth: AuthConfig,
 }
 
 impl Config {
@@ -24,6 +82,11 @@ impl Config {
         &self.db.url
     }
 
+    // How long a request waits for a database connection before it is answered with a 503
+    pub fn db_pool_timeout(&self) -> Duration {
+        Duration::from_secs(self.db.pool_timeout_secs)
+    }
+
     pub fn server_port(&self) -> u16 {
         self.server.port
     }
@@ -31,10 +94,185 @@ impl Config {
     pub fn server_host(&self) -> &str {
         &self.server.host
     }
+
+    pub fn trust_forwarded_for(&self) -> bool {
+        self.server.trust_forwarded_for
+    }
+
+    pub fn trash_retention(&self) -> chrono::Duration {
+        chrono::Duration::days(self.posts.trash_retention_days)
+    }
+
+    pub fn trash_purge_interval(&self) -> Duration {
+        Duration::from_secs(self.posts.trash_purge_interval_secs)
+    }
+
+    pub fn publish_scheduler_interval(&self) -> Duration {
+        Duration::from_secs(self.posts.publish_scheduler_interval_secs)
+    }
+
+    pub fn post_title_max_chars(&self) -> usize {
+        self.posts.title_max_chars
+    }
+
+    // Post routes accept request bodies of twice this, see `post_request_max_bytes`
+    pub fn post_body_max_bytes(&self) -> usize {
+        self.posts.body_max_bytes
+    }
+
+    // Enough for a body at the limit once escaped as JSON, along with the other fields, so that
+    // one over the limit gets a field error rather than a bare 413
+    pub fn post_request_max_bytes(&self) -> usize {
+        self.posts.body_max_bytes.saturating_mul(2).saturating_add(64 * 1024)
+    }
+
+    pub fn session_idle_timeout(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.auth.session_idle_timeout_secs)
+    }
+
+    pub fn session_max_lifetime(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.auth.session_max_lifetime_secs)
+    }
+
+    pub fn oauth_provider(&self, name: &str) -> Option<&OAuthProviderConfig> {
+        self.auth.oauth_providers.iter().find(|provider| provider.name == name)
+    }
+
+    // The provider `/api/auth/login` uses when none is given, the first one configured
+    pub fn default_oauth_provider(&self) -> Option<&OAuthProviderConfig> {
+        self.auth.oauth_providers.first()
+    }
+
+    // The provider `/api/auth/oauth_return` belongs to: the one set up through Google's old
+    // variables, or else the default one
+    pub fn legacy_oauth_provider(&self) -> Option<&OAuthProviderConfig> {
+        self.auth
+            .oauth_providers
+            .iter()
+            .find(|provider| provider.legacy_redirect)
+            .or_else(|| self.default_oauth_provider())
+    }
+
+    // Revoking needs the provider's access token at logout, so while this is on it is kept with
+    // the session as is. Unlike the session secret it can't be stored as a digest, and it isn't
+    // encrypted either: anyone who can read user_sessions can use the grant until it expires.
+    pub fn revoke_on_logout(&self) -> bool {
+        self.auth.revoke_on_logout
+    }
+
+    pub fn revocation_stub(&self) -> bool {
+        self.auth.revocation_stub
+    }
+
+    pub fn oauth_state_ttl(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.auth.oauth_state_ttl_secs)
+    }
+
+    pub fn auth_janitor_interval(&self) -> Duration {
+        Duration::from_secs(self.auth.janitor_interval_secs)
+    }
+
+    pub fn token_default_lifetime_days(&self) -> i64 {
+        self.auth.token_default_lifetime_days
+    }
+
+    pub fn token_max_lifetime_days(&self) -> i64 {
+        self.auth.token_max_lifetime_days
+    }
+
+    // Origins besides our own that logins may return to, e.g. https://app.example.com
+    pub fn return_url_allowed_origins(&self) -> &[String] {
+        &self.auth.return_url_allowed_origins
+    }
 }
 
 pub static CONFIG: OnceCell<Config> = OnceCell::const_new();
 
+// Read OAUTH_<PROVIDER>_<KEY>, e.g. OAUTH_GITHUB_CLIENT_ID
+fn provider_var(name: &str, key: &str) -> Option<String> {
+    env::var(format!("OAUTH_{}_{}", name.to_uppercase(), key)).ok()
+}
+
+fn init_oauth_provider(name: &str) -> OAuthProviderConfig {
+    let kind = match provider_var(name, "KIND").as_deref().unwrap_or(name) {
+        "google" => OAuthProviderKind::Google,
+        "github" => OAuthProviderKind::GitHub,
+        "gitlab" => OAuthProviderKind::GitLab,
+        _ => OAuthProviderKind::Oidc,
+    };
+
+    let issuer_url = provider_var(name, "ISSUER_URL").or_else(|| match kind {
+        OAuthProviderKind::GitLab => Some(String::from("https://gitlab.com")),
+        _ => None,
+    });
+
+    let (auth_url, token_url, userinfo_url, emails_url, revocation_url, scopes) = match kind {
+        OAuthProviderKind::Google => (
+            Some(String::from("https://accounts.google.com/o/oauth2/v2/auth")),
+            Some(String::from("https://www.googleapis.com/oauth2/v3/token")),
+            Some(String::from("https://www.googleapis.com/oauth2/v2/userinfo")),
+            None,
+            Some(String::from("https://oauth2.googleapis.com/revoke")),
+            "https://www.googleapis.com/auth/userinfo.email",
+        ),
+        OAuthProviderKind::GitHub => (
+            Some(String::from("https://github.com/login/oauth/authorize")),
+            Some(String::from("https://github.com/login/oauth/access_token")),
+            Some(String::from("https://api.github.com/user")),
+            Some(String::from("https://api.github.com/user/emails")),
+            None,
+            "user:email",
+        ),
+        OAuthProviderKind::GitLab => {
+            let base_url = issuer_url.clone().unwrap_or_default();
+            (
+                Some(format!("{}/oauth/authorize", base_url)),
+                Some(format!("{}/oauth/token", base_url)),
+                Some(format!("{}/oauth/userinfo", base_url)),
+                None,
+                Some(format!("{}/oauth/revoke", base_url)),
+                "openid email",
+            )
+        }
+        OAuthProviderKind::Oidc => (None, None, None, None, None, "openid email"),
+    };
+
+    // Google was the only provider once, its old variables still work
+    let (legacy_client_id, legacy_client_secret, legacy_revocation_url) = match kind {
+        OAuthProviderKind::Google => (
+            env::var("GOOGLE_CLIENT_ID").ok(),
+            env::var("GOOGLE_CLIENT_SECRET").ok(),
+            env::var("GOOGLE_REVOCATION_URL").ok(),
+        ),
+        _ => (None, None, None),
+    };
+
+    let client_id = provider_var(name, "CLIENT_ID");
+    let legacy_redirect = client_id.is_none() && legacy_client_id.is_some();
+
+    OAuthProviderConfig {
+        name: name.to_string(),
+        kind,
+        client_id: client_id.or(legacy_client_id),
+        client_secret: provider_var(name, "CLIENT_SECRET").or(legacy_client_secret),
+        issuer_url,
+        auth_url: provider_var(name, "AUTH_URL").or(auth_url),
+        token_url: provider_var(name, "TOKEN_URL").or(token_url),
+        userinfo_url: provider_var(name, "USERINFO_URL").or(userinfo_url),
+        emails_url: provider_var(name, "EMAILS_URL").or(emails_url),
+        revocation_url: provider_var(name, "REVOCATION_URL")
+            .or(legacy_revocation_url)
+            .or(revocation_url),
+        scopes: provider_var(name, "SCOPES")
+            .unwrap_or_else(|| String::from(scopes))
+            .split([' ', ','])
+            .filter(|scope| !scope.is_empty())
+            .map(String::from)
+            .collect(),
+        legacy_redirect,
+    }
+}
+
 async fn init_config() -> Config {
     // Load environment variables from a .env file if present
     dotenv().ok();
@@ -45,15 +283,99 @@ async fn init_config() -> Config {
             .unwrap_or_else(|_| String::from("8080"))
             .parse::<u16>()
             .unwrap(),
+        trust_forwarded_for: env::var("TRUST_FORWARDED_FOR")
+            .unwrap_or_else(|_| String::from("false"))
+            .parse::<bool>()
+            .unwrap(),
     };
 
     let database_config = DatabaseConfig {
         url: env::var("DATABASE_URL").expect("DATABASE_URL must be set"),
+        pool_timeout_secs: env::var("DB_POOL_TIMEOUT_SECS")
+            .unwrap_or_else(|_| String::from("5"))
+            .parse::<u64>()
+            .unwrap(),
+    };
+
+    let posts_config = PostsConfig {
+        trash_retention_days: env::var("TRASH_RETENTION_DAYS")
+            .unwrap_or_else(|_| String::from("30"))
+            .parse::<i64>()
+            .unwrap(),
+        trash_purge_interval_secs: env::var("TRASH_PURGE_INTERVAL_SECS")
+            .unwrap_or_else(|_| String::from("3600"))
+            .parse::<NonZeroU64>()
+            .expect("TRASH_PURGE_INTERVAL_SECS must be a whole number of seconds above 0")
+            .get(),
+        publish_scheduler_interval_secs: env::var("PUBLISH_SCHEDULER_INTERVAL_SECS")
+            .unwrap_or_else(|_| String::from("30"))
+            .parse::<NonZeroU64>()
+            .expect("PUBLISH_SCHEDULER_INTERVAL_SECS must be a whole number of seconds above 0")
+            .get(),
+        title_max_chars: env::var("POST_TITLE_MAX_CHARS")
+            .unwrap_or_else(|_| String::from("200"))
+            .parse::<usize>()
+            .unwrap(),
+        body_max_bytes: env::var("POST_BODY_MAX_BYTES")
+            .unwrap_or_else(|_| String::from("1048576"))
+            .parse::<usize>()
+            .unwrap(),
+    };
+
+    let auth_config = AuthConfig {
+        oauth_providers: env::var("OAUTH_PROVIDERS")
+            .unwrap_or_else(|_| String::from("google"))
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| init_oauth_provider(&name.to_lowercase()))
+            .collect(),
+        session_idle_timeout_secs: env::var("SESSION_IDLE_TIMEOUT_SECS")
+            .unwrap_or_else(|_| String::from("86400"))
+            .parse::<i64>()
+            .unwrap(),
+        session_max_lifetime_secs: env::var("SESSION_MAX_LIFETIME_SECS")
+            .unwrap_or_else(|_| String::from("2592000"))
+            .parse::<i64>()
+            .unwrap(),
+        revoke_on_logout: env::var("OAUTH_REVOKE_ON_LOGOUT")
+            .unwrap_or_else(|_| String::from("false"))
+            .parse::<bool>()
+            .unwrap(),
+        revocation_stub: env::var("OAUTH_REVOCATION_STUB")
+            .unwrap_or_else(|_| String::from("false"))
+            .parse::<bool>()
+            .unwrap(),
+        oauth_state_ttl_secs: env::var("OAUTH_STATE_TTL_SECS")
+            .unwrap_or_else(|_| String::from("600"))
+            .parse::<i64>()
+            .unwrap(),
+        janitor_interval_secs: env::var("AUTH_JANITOR_INTERVAL_SECS")
+            .unwrap_or_else(|_| String::from("3600"))
+            .parse::<NonZeroU64>()
+            .expect("AUTH_JANITOR_INTERVAL_SECS must be a whole number of seconds above 0")
+            .get(),
+        token_default_lifetime_days: env::var("TOKEN_DEFAULT_LIFETIME_DAYS")
+            .unwrap_or_else(|_| String::from("30"))
+            .parse::<i64>()
+            .unwrap(),
+        token_max_lifetime_days: env::var("TOKEN_MAX_LIFETIME_DAYS")
+            .unwrap_or_else(|_| String::from("365"))
+            .parse::<i64>()
+            .unwrap(),
+        return_url_allowed_origins: env::var("RETURN_URL_ALLOWED_ORIGINS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|origin| origin.trim().trim_end_matches('/').to_lowercase())
+            .filter(|origin| !origin.is_empty())
+            .collect(),
     };
 
     Config {
         server: server_config,
         db: database_config,
+        posts: posts_config,
+        auth: auth_config,
     }
 }
 
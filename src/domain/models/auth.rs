@@ -1,40 +1,209 @@
+use crate::domain::models::role::Permission;
+use crate::domain::problem::Problem;
 use crate::infra::errors::{Error, InfraError};
-use axum::http::StatusCode;
+use axum::http::{header, HeaderValue, StatusCode};
 use axum::response::{IntoResponse, Response};
-use axum::Json;
-use serde_json::json;
+use tracing::log::{log, Level};
+use uuid::Uuid;
 
 #[derive(Debug)]
 pub enum AuthError {
+    // The OAuth redirect back from the provider
+    MissingOAuthState,
+    UnknownOAuthState,
+    ExpiredOAuthState,
+    MissingOAuthCode,
+    ProviderError {
+        error: String,
+        description: Option<String>,
+    },
+    TokenExchangeFailed(String),
+    UserinfoFailed(String),
     EmailAddressIsNotVerified,
+    // Who may do what
     Unauthenticated,
+    SessionRequired,
+    InsufficientScope,
+    MissingPermission(Permission),
+    // Requests that can't be served
+    SessionNotFound(Uuid),
+    UnknownProvider(String),
+    IdentityNotFound(Uuid),
+    LastIdentity,
+    ProviderAlreadyLinked(String),
+    IdentityLinkedToAnotherUser,
+    EmailTaken,
+    TokenNotFound(Uuid),
+    InvalidToken(String),
+    InvalidReturnUrl(String),
     InfraError(InfraError),
 }
 
+impl AuthError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::MissingOAuthState
+            | Self::UnknownOAuthState
+            | Self::ExpiredOAuthState
+            | Self::MissingOAuthCode
+            | Self::InvalidReturnUrl(_) => StatusCode::BAD_REQUEST,
+            // The user said no at the provider
+            Self::ProviderError { error, .. } if error == "access_denied" => StatusCode::FORBIDDEN,
+            Self::ProviderError { .. } | Self::TokenExchangeFailed(_) | Self::UserinfoFailed(_) => {
+                StatusCode::BAD_GATEWAY
+            }
+            Self::Unauthenticated => StatusCode::UNAUTHORIZED,
+            Self::EmailAddressIsNotVerified
+            | Self::SessionRequired
+            | Self::InsufficientScope
+            | Self::MissingPermission(_) => StatusCode::FORBIDDEN,
+            Self::SessionNotFound(_)
+            | Self::UnknownProvider(_)
+            | Self::IdentityNotFound(_)
+            | Self::TokenNotFound(_) => StatusCode::NOT_FOUND,
+            Self::LastIdentity
+            | Self::ProviderAlreadyLinked(_)
+            | Self::IdentityLinkedToAnotherUser
+            | Self::EmailTaken => StatusCode::CONFLICT,
+            Self::InvalidToken(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::InfraError(db_error) => db_error.status(),
+        }
+    }
+
+    // Stable identifier of the error for clients to switch on, unlike the message
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::MissingOAuthState => "missing_state",
+            Self::UnknownOAuthState => "unknown_state",
+            Self::ExpiredOAuthState => "expired_state",
+            Self::MissingOAuthCode => "missing_code",
+            Self::ProviderError { error, .. } if error == "access_denied" => "consent_denied",
+            Self::ProviderError { .. } => "provider_error",
+            Self::TokenExchangeFailed(_) => "token_exchange_failed",
+            Self::UserinfoFailed(_) => "userinfo_failed",
+            Self::EmailAddressIsNotVerified => "email_not_verified",
+            Self::Unauthenticated => "unauthenticated",
+            Self::SessionRequired => "session_required",
+            Self::InsufficientScope => "insufficient_scope",
+            Self::MissingPermission(_) => "forbidden",
+            Self::SessionNotFound(_) => "session_not_found",
+            Self::UnknownProvider(_) => "unknown_provider",
+            Self::IdentityNotFound(_) => "identity_not_found",
+            Self::LastIdentity => "last_identity",
+            Self::ProviderAlreadyLinked(_) => "provider_already_linked",
+            Self::IdentityLinkedToAnotherUser => "identity_linked_to_another_user",
+            Self::EmailTaken => "email_taken",
+            Self::TokenNotFound(_) => "token_not_found",
+            Self::InvalidToken(_) => "invalid_token",
+            Self::InvalidReturnUrl(_) => "invalid_return_url",
+            Self::InfraError(db_error) => db_error.code(),
+        }
+    }
+
+    // Failures on our or the provider's side are worth an operator's attention, users going
+    // astray are not
+    fn log_level(&self) -> Level {
+        match self {
+            Self::ProviderError { error, .. } if error == "access_denied" => Level::Info,
+            Self::ProviderError { .. } | Self::TokenExchangeFailed(_) | Self::UserinfoFailed(_) => {
+                Level::Warn
+            }
+            Self::MissingOAuthState
+            | Self::UnknownOAuthState
+            | Self::ExpiredOAuthState
+            | Self::MissingOAuthCode
+            | Self::EmailAddressIsNotVerified
+            | Self::InvalidReturnUrl(_) => Level::Info,
+            _ => Level::Debug,
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            Self::MissingOAuthState => "OAuth: the provider sent no state".to_string(),
+            Self::UnknownOAuthState => {
+                "OAuth: unknown login, it may have been finished already".to_string()
+            }
+            Self::ExpiredOAuthState => "OAuth: login took too long, please try again".to_string(),
+            Self::MissingOAuthCode => "OAuth: the provider sent no authorization code".to_string(),
+            Self::ProviderError { error, .. } if error == "access_denied" => {
+                "OAuth: access was denied at the provider".to_string()
+            }
+            Self::ProviderError { error, description } => match description {
+                Some(description) => {
+                    format!("OAuth: the provider failed with {}: {}", error, description)
+                }
+                None => format!("OAuth: the provider failed with {}", error),
+            },
+            // The details only go to the log, they may say more about the provider than users
+            // need to know
+            Self::TokenExchangeFailed(_) => {
+                "OAuth: the provider didn't accept the authorization code".to_string()
+            }
+            Self::UserinfoFailed(_) => {
+                "OAuth: the provider didn't tell who the user is".to_string()
+            }
+            Self::EmailAddressIsNotVerified => "OAuth: email address is not verified".to_string(),
+            Self::Unauthenticated => "User not logged in or unauthorized".to_string(),
+            Self::SessionRequired => {
+                "This can only be done from a browser session, not with a token".to_string()
+            }
+            Self::InsufficientScope => "The token doesn't have the scope this requires".to_string(),
+            Self::MissingPermission(permission) => {
+                format!("Missing permission {}", permission.as_str())
+            }
+            Self::SessionNotFound(id) => format!("Session with id {} has not been found", id),
+            Self::UnknownProvider(provider) => format!("OAuth: unknown provider {}", provider),
+            Self::IdentityNotFound(id) => format!("Identity with id {} has not been found", id),
+            Self::LastIdentity => "The last identity of a user can't be unlinked".to_string(),
+            Self::ProviderAlreadyLinked(provider) => {
+                format!("An identity from {} is already linked", provider)
+            }
+            Self::IdentityLinkedToAnotherUser => {
+                "This identity is linked to another user".to_string()
+            }
+            Self::EmailTaken => {
+                "A user with this email address exists, log in and link this provider instead"
+                    .to_string()
+            }
+            Self::TokenNotFound(id) => format!("Token with id {} has not been found", id),
+            Self::InvalidToken(reason) => format!("Invalid token: {}", reason),
+            Self::InvalidReturnUrl(return_url) => format!(
+                "return_url {:?} is neither a path on this site nor on an allowed origin",
+                return_url
+            ),
+            Self::InfraError(db_error) => db_error.message().to_string(),
+        }
+    }
+}
+
 impl IntoResponse for AuthError {
     fn into_response(self) -> Response {
-        let (status, err_msg) = match self {
-            Self::InfraError(db_error) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Internal server error: {}", db_error),
-            ),
-            Self::EmailAddressIsNotVerified => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "OAuth: email address is not verified".to_string(),
-            ),
-            Self::Unauthenticated => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "User not logged in or unauthorized".to_string(),
-            ),
+        if let Self::InfraError(db_error) = self {
+            return db_error.into_response();
+        }
+
+        let status = self.status();
+        let code = self.code();
+        let err_msg = self.message();
+
+        // Provider failures are logged with what the provider said, which users don't get to see
+        let details = match &self {
+            Self::TokenExchangeFailed(details) | Self::UserinfoFailed(details) => details.as_str(),
+            _ => err_msg.as_str(),
         };
+        log!(self.log_level(), "AuthError {}: {}", code, details);
 
-        (
-            status,
-            Json(
-                json!({"resource":"Auth", "message": err_msg, "happened_at" : chrono::Utc::now() }),
-            ),
-        )
-            .into_response()
+        let mut response = Problem::new(status, code, err_msg).into_response();
+
+        // Tell clients how to authenticate
+        if status == StatusCode::UNAUTHORIZED {
+            response
+                .headers_mut()
+                .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        }
+
+        response
     }
 }
 
@@ -0,0 +1,78 @@
+use crate::domain::problem::Problem;
+use crate::infra::errors::InfraError;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommentModel {
+    pub id: Uuid,
+    pub post_id: Uuid,
+    pub author_id: Option<Uuid>,
+    pub author_email: Option<String>,
+    pub parent_id: Option<Uuid>,
+    pub root_id: Uuid,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+// A page of threads: the top-level comments of the page with all of their replies
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommentsPage {
+    pub comments: Vec<CommentModel>,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum CommentError {
+    NotFound(Uuid),
+    PostNotFound(Uuid),
+    ParentNotFound(Uuid),
+    EmptyBody,
+    Unauthenticated,
+    Forbidden(Uuid),
+    InfraError(InfraError),
+}
+
+impl IntoResponse for CommentError {
+    fn into_response(self) -> Response {
+        let (status, code, err_msg) = match self {
+            Self::NotFound(id) => (
+                StatusCode::NOT_FOUND,
+                "comment_not_found",
+                format!("CommentModel with id {} has not been found", id),
+            ),
+            Self::PostNotFound(id) => (
+                StatusCode::NOT_FOUND,
+                "post_not_found",
+                format!("PostModel with id {} has not been found", id),
+            ),
+            Self::ParentNotFound(id) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "parent_not_found",
+                format!("Comment {} to reply to does not exist on this post", id),
+            ),
+            Self::EmptyBody => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "empty_body",
+                String::from("Comment body must not be empty"),
+            ),
+            Self::Unauthenticated => (
+                StatusCode::UNAUTHORIZED,
+                "unauthenticated",
+                String::from("User not logged in"),
+            ),
+            Self::Forbidden(id) => (
+                StatusCode::FORBIDDEN,
+                "forbidden",
+                format!("CommentModel with id {} belongs to another author", id),
+            ),
+            Self::InfraError(db_error) => return db_error.into_response(),
+        };
+
+        Problem::new(status, code, err_msg).into_response()
+    }
+}
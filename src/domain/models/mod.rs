@@ -1,4 +1,10 @@
 pub mod auth;
+pub mod comment;
+pub mod personal_access_token;
 pub mod post;
+pub mod post_revision;
+pub mod role;
+pub mod tag;
 pub mod user;
+pub mod user_identity;
 pub mod user_session;
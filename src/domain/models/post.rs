@@ -1,8 +1,10 @@
+use crate::domain::models::auth::AuthError;
+use crate::domain::problem::Problem;
 use crate::infra::errors::InfraError;
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
-use axum::Json;
-use serde_json::json;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
 use uuid::Uuid;
 
 #[derive(Clone, Debug, PartialEq)]
@@ -11,37 +13,104 @@ pub struct PostModel {
     pub title: String,
     pub body: String,
     pub published: bool,
+    pub author_id: Option<Uuid>,
+    pub author_email: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub published_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub publish_at: Option<DateTime<Utc>>,
+    pub slug: String,
+    pub tags: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PostsPage {
+    pub posts: Vec<PostModel>,
+    pub snippets: HashMap<Uuid, String>,
+    pub next_cursor: Option<String>,
+    pub total: Option<i64>,
 }
 
 #[derive(Debug)]
 pub enum PostError {
-    InternalServerError,
     NotFound(Uuid),
+    SlugNotFound(String),
+    SlugTaken(String),
+    InvalidSlug,
+    InvalidTag(String),
+    RevisionNotFound(Uuid, i32),
+    InvalidCursor,
+    AlreadyPublished(Uuid),
+    PublishAtInPast,
+    Forbidden(Uuid),
+    AuthError(AuthError),
     InfraError(InfraError),
 }
 
 impl IntoResponse for PostError {
     fn into_response(self) -> Response {
-        let (status, err_msg) = match self {
+        let (status, code, err_msg) = match self {
             Self::NotFound(id) => (
                 StatusCode::NOT_FOUND,
+                "post_not_found",
                 format!("PostModel with id {} has not been found", id),
             ),
-            Self::InfraError(db_error) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Internal server error: {}", db_error),
+            Self::SlugNotFound(slug) => (
+                StatusCode::NOT_FOUND,
+                "post_not_found",
+                format!("PostModel with slug {} has not been found", slug),
+            ),
+            Self::SlugTaken(slug) => (
+                StatusCode::CONFLICT,
+                "slug_taken",
+                format!("Slug {} is already used by another post", slug),
+            ),
+            Self::InvalidSlug => (
+                StatusCode::BAD_REQUEST,
+                "invalid_slug",
+                String::from("Slug must contain at least one letter or digit"),
             ),
-            _ => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                String::from("Internal server error"),
+            Self::InvalidTag(tag) => (
+                StatusCode::BAD_REQUEST,
+                "invalid_tag",
+                format!("Tag {:?} must contain at least one letter or digit", tag),
+            ),
+            Self::RevisionNotFound(id, revision) => (
+                StatusCode::NOT_FOUND,
+                "revision_not_found",
+                format!("PostModel with id {} has no revision {}", id, revision),
             ),
+            Self::InvalidCursor => (
+                StatusCode::BAD_REQUEST,
+                "invalid_cursor",
+                String::from("Cursor was issued for a different sort order"),
+            ),
+            Self::AlreadyPublished(id) => (
+                StatusCode::CONFLICT,
+                "already_published",
+                format!("PostModel with id {} is already published", id),
+            ),
+            Self::PublishAtInPast => (
+                StatusCode::BAD_REQUEST,
+                "publish_at_in_past",
+                String::from("publish_at must be in the future"),
+            ),
+            Self::Forbidden(id) => (
+                StatusCode::FORBIDDEN,
+                "forbidden",
+                format!("PostModel with id {} belongs to another author", id),
+            ),
+            Self::AuthError(auth_error) => return auth_error.into_response(),
+            Self::InfraError(db_error) => return db_error.into_response(),
         };
 
-        (            status,
-                     Json(
-                         json!({"resource":"PostModel", "message": err_msg, "happened_at" : chrono::Utc::now() }),
-                     ),
-        )
-            .into_response()
+        Problem::new(status, code, err_msg).into_response()
+    }
+}
+
+impl From<AuthError> for PostError {
+    fn from(err: AuthError) -> Self {
+        Self::AuthError(err)
     }
 }
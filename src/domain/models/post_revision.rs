@@ -0,0 +1,14 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PostRevisionModel {
+    pub id: Uuid,
+    pub post_id: Uuid,
+    pub revision: i32,
+    pub title: String,
+    pub body: String,
+    pub editor_id: Option<Uuid>,
+    pub restored_from: Option<i32>,
+    pub created_at: DateTime<Utc>,
+}
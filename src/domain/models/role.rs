@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+// What a user may do is decided by their role, each role granting a fixed set of permissions
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Admin,
+    Editor,
+    Reader,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Permission {
+    PostCreate,
+    PostPublish,
+    PostEditAny,
+    PostDeleteAny,
+    UserManage,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Admin => "admin",
+            Self::Editor => "editor",
+            Self::Reader => "reader",
+        }
+    }
+
+    pub fn parse(role: &str) -> Option<Self> {
+        match role {
+            "admin" => Some(Self::Admin),
+            "editor" => Some(Self::Editor),
+            "reader" => Some(Self::Reader),
+            _ => None,
+        }
+    }
+
+    // Readers may only comment, which any logged-in user can
+    pub fn permissions(&self) -> Vec<Permission> {
+        match self {
+            Self::Admin => vec![
+                Permission::PostCreate,
+                Permission::PostPublish,
+                Permission::PostEditAny,
+                Permission::PostDeleteAny,
+                Permission::UserManage,
+            ],
+            Self::Editor => vec![Permission::PostCreate, Permission::PostPublish],
+            Self::Reader => vec![],
+        }
+    }
+}
+
+impl Permission {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PostCreate => "post:create",
+            Self::PostPublish => "post:publish",
+            Self::PostEditAny => "post:edit:any",
+            Self::PostDeleteAny => "post:delete:any",
+            Self::UserManage => "user:manage",
+        }
+    }
+}
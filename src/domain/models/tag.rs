@@ -0,0 +1,21 @@
+use crate::infra::errors::InfraError;
+use axum::response::{IntoResponse, Response};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TagModel {
+    pub name: String,
+    pub post_count: i64,
+}
+
+#[derive(Debug)]
+pub enum TagError {
+    InfraError(InfraError),
+}
+
+impl IntoResponse for TagError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::InfraError(db_error) => db_error.into_response(),
+        }
+    }
+}
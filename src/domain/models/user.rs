@@ -1,7 +1,57 @@
+use crate::domain::models::role::Role;
+use crate::domain::problem::Problem;
+use crate::infra::errors::InfraError;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct UserModel {
     pub id: Uuid,
     pub email: String,
+    pub role: Role,
+    pub created_at: DateTime<Utc>,
+    pub suspended_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct UsersPage {
+    pub users: Vec<UserModel>,
+    pub next_cursor: Option<String>,
+}
+
+// A user as admins see them, with how active they are
+#[derive(Clone, Debug, PartialEq)]
+pub struct UserOverviewModel {
+    pub user: UserModel,
+    pub session_count: i64,
+    pub post_count: i64,
+}
+
+#[derive(Debug)]
+pub enum UserError {
+    NotFound(Uuid),
+    OwnAccount,
+    InfraError(InfraError),
+}
+
+impl IntoResponse for UserError {
+    fn into_response(self) -> Response {
+        let (status, code, err_msg) = match self {
+            Self::NotFound(id) => (
+                StatusCode::NOT_FOUND,
+                "user_not_found",
+                format!("UserModel with id {} has not been found", id),
+            ),
+            Self::OwnAccount => (
+                StatusCode::CONFLICT,
+                "own_account",
+                String::from("Admins can't suspend or demote themselves"),
+            ),
+            Self::InfraError(db_error) => return db_error.into_response(),
+        };
+
+        Problem::new(status, code, err_msg).into_response()
+    }
 }
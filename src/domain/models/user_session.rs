@@ -5,7 +5,14 @@ pub struct UserSessionModel {
     pub id: Uuid,
     pub user_id: Uuid,
     pub session_token_p1: String,
-    pub session_token_p2: String,
+    pub session_token_p2_digest: String,
     pub created_at: i64,
     pub expires_at: i64,
+    pub oauth_access_token: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub last_seen_at: i64,
+    pub previous_session_token_p2_digest: Option<String>,
+    pub rotated_at: Option<i64>,
+    pub oauth_provider: Option<String>,
 }
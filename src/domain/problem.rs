@@ -0,0 +1,84 @@
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+
+const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+// The request being served, so problems can point at it without every error having to carry it
+#[derive(Clone, Debug)]
+pub struct RequestContext {
+    pub request_id: String,
+    pub instance: String,
+}
+
+tokio::task_local! {
+    pub static REQUEST_CONTEXT: RequestContext;
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+// An error response as described by RFC 7807. Besides the standard members it has a
+// machine-readable `code`, which `type` is derived from, the id of the request and, for invalid
+// input, what is wrong with which field.
+#[derive(Debug, Serialize)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    problem_type: String,
+    title: String,
+    status: u16,
+    detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    instance: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+    code: &'static str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<FieldError>,
+}
+
+impl Problem {
+    pub fn new(status: StatusCode, code: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            problem_type: format!("/problems/{}", code.replace('_', "-")),
+            title: status.canonical_reason().unwrap_or("Error").to_string(),
+            status: status.as_u16(),
+            detail: detail.into(),
+            instance: None,
+            request_id: None,
+            code,
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn with_errors(mut self, errors: Vec<FieldError>) -> Self {
+        self.errors = errors;
+        self
+    }
+}
+
+impl IntoResponse for Problem {
+    fn into_response(mut self) -> Response {
+        // Outside of a request there is nothing to point at
+        if let Ok(context) = REQUEST_CONTEXT.try_with(RequestContext::clone) {
+            self.instance = Some(context.instance);
+            self.request_id = Some(context.request_id);
+        }
+
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let body = serde_json::to_string(&self).unwrap_or_default();
+
+        (
+            status,
+            [(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static(PROBLEM_CONTENT_TYPE),
+            )],
+            body,
+        )
+            .into_response()
+    }
+}
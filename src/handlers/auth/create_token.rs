@@ -0,0 +1,94 @@
+use crate::config::config;
+use crate::domain::models::auth::AuthError;
+use crate::handlers::auth::{
+    adapt_token_to_token_response, digest_secret, CreateTokenRequest, CreatedTokenResponse,
+    UserData,
+};
+use crate::handlers::extractors::Json;
+use crate::infra::repositories::personal_access_tokens_repository::{
+    self, NewPersonalAccessTokenDb,
+};
+use crate::AppState;
+use axum::{extract::State, http::StatusCode, Extension};
+use chrono::{Duration, Utc};
+use tracing::log::debug;
+use uuid::Uuid;
+
+// Marks a bearer token as one of ours, and makes leaked ones easy to scan for
+pub const TOKEN_PREFIX: &str = "pat_";
+
+const MAX_TOKEN_NAME_LENGTH: usize = 100;
+const TOKEN_PREFIX_LENGTH: usize = TOKEN_PREFIX.len() + 8;
+
+pub async fn create_token(
+    State(state): State<AppState>,
+    Extension(user_data): Extension<Option<UserData>>,
+    Json(new_token): Json<CreateTokenRequest>,
+) -> Result<(StatusCode, Json<CreatedTokenResponse>), AuthError> {
+    debug!("->> {:<12} - create_token", "HANDLER");
+
+    let user_data = user_data.ok_or(AuthError::Unauthenticated)?;
+    user_data.session_id()?;
+
+    let name = new_token.name.trim().to_string();
+    if name.is_empty() || name.chars().count() > MAX_TOKEN_NAME_LENGTH {
+        return Err(AuthError::InvalidToken(format!(
+            "name must be between 1 and {} characters",
+            MAX_TOKEN_NAME_LENGTH
+        )));
+    }
+
+    let mut scopes = new_token.scopes;
+    scopes.sort_by_key(|scope| scope.as_str());
+    scopes.dedup();
+    if scopes.is_empty() {
+        return Err(AuthError::InvalidToken(
+            "at least one scope is required".to_string(),
+        ));
+    }
+
+    let config = config().await;
+    let max_lifetime_days = config.token_max_lifetime_days();
+    let lifetime_days = new_token
+        .expires_in_days
+        .unwrap_or(config.token_default_lifetime_days());
+    if !(1..=max_lifetime_days).contains(&lifetime_days) {
+        return Err(AuthError::InvalidToken(format!(
+            "expires_in_days must be between 1 and {}",
+            max_lifetime_days
+        )));
+    }
+
+    // Two random UUIDs give the token plenty of entropy
+    let token = format!(
+        "{}{}{}",
+        TOKEN_PREFIX,
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    );
+
+    let created = personal_access_tokens_repository::insert(
+        &state.pool,
+        NewPersonalAccessTokenDb {
+            user_id: user_data.user_id,
+            name,
+            scopes: scopes
+                .iter()
+                .map(|scope| scope.as_str().to_string())
+                .collect(),
+            token_prefix: token[..TOKEN_PREFIX_LENGTH].to_string(),
+            token_digest: digest_secret(&token),
+            expires_at: Utc::now() + Duration::days(lifetime_days),
+        },
+    )
+    .await
+    .map_err(AuthError::InfraError)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedTokenResponse {
+            token,
+            details: adapt_token_to_token_response(created),
+        }),
+    ))
+}
@@ -0,0 +1,33 @@
+use crate::config::config;
+use crate::domain::models::auth::AuthError;
+use crate::handlers::auth::login::start_login;
+use crate::handlers::auth::{LoginParams, UserData};
+use crate::handlers::extractors::{Path, Query};
+use crate::AppState;
+use axum::{
+    extract::{Host, State},
+    response::Redirect,
+    Extension,
+};
+use tracing::log::debug;
+
+// Go through the login of a provider to link the identity there to the logged-in user
+pub async fn link_identity(
+    Extension(user_data): Extension<Option<UserData>>,
+    Path(provider): Path<String>,
+    Query(params): Query<LoginParams>,
+    State(state): State<AppState>,
+    Host(hostname): Host,
+) -> Result<Redirect, AuthError> {
+    debug!("->> {:<12} - link_identity", "HANDLER");
+
+    let user_data = user_data.ok_or(AuthError::Unauthenticated)?;
+    user_data.session_id()?;
+
+    let provider = config()
+        .await
+        .oauth_provider(&provider)
+        .ok_or(AuthError::UnknownProvider(provider))?;
+
+    start_login(provider, params, state, hostname, Some(user_data.user_id)).await
+}
@@ -0,0 +1,30 @@
+use crate::domain::models::auth::AuthError;
+use crate::handlers::auth::{
+    adapt_identity_to_identity_response, ListIdentitiesResponse, UserData,
+};
+use crate::handlers::extractors::Json;
+use crate::infra::repositories::user_identities_repository;
+use crate::AppState;
+use axum::{extract::State, Extension};
+use tracing::log::debug;
+
+pub async fn list_identities(
+    State(state): State<AppState>,
+    Extension(user_data): Extension<Option<UserData>>,
+) -> Result<Json<ListIdentitiesResponse>, AuthError> {
+    debug!("->> {:<12} - list_identities", "HANDLER");
+
+    let user_data = user_data.ok_or(AuthError::Unauthenticated)?;
+    user_data.session_id()?;
+
+    let identities = user_identities_repository::get_all_by_user(&state.pool, user_data.user_id)
+        .await
+        .map_err(AuthError::InfraError)?;
+
+    Ok(Json(ListIdentitiesResponse {
+        identities: identities
+            .into_iter()
+            .map(adapt_identity_to_identity_response)
+            .collect(),
+    }))
+}
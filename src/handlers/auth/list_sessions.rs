@@ -0,0 +1,33 @@
+use crate::domain::models::auth::AuthError;
+use crate::handlers::auth::{adapt_session_to_session_response, ListSessionsResponse, UserData};
+use crate::handlers::extractors::Json;
+use crate::infra::repositories::user_sessions_repository;
+use crate::AppState;
+use axum::{extract::State, Extension};
+use chrono::Utc;
+use tracing::log::debug;
+
+pub async fn list_sessions(
+    State(state): State<AppState>,
+    Extension(user_data): Extension<Option<UserData>>,
+) -> Result<Json<ListSessionsResponse>, AuthError> {
+    debug!("->> {:<12} - list_sessions", "HANDLER");
+
+    let user_data = user_data.ok_or(AuthError::Unauthenticated)?;
+    let session_id = user_data.session_id()?;
+
+    let sessions = user_sessions_repository::get_all_by_user(
+        &state.pool,
+        user_data.user_id,
+        Utc::now().timestamp(),
+    )
+    .await
+    .map_err(AuthError::InfraError)?;
+
+    Ok(Json(ListSessionsResponse {
+        sessions: sessions
+            .into_iter()
+            .map(|session| adapt_session_to_session_response(session, session_id))
+            .collect(),
+    }))
+}
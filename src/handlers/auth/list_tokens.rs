@@ -0,0 +1,33 @@
+use crate::domain::models::auth::AuthError;
+use crate::handlers::auth::{adapt_token_to_token_response, ListTokensResponse, UserData};
+use crate::handlers::extractors::Json;
+use crate::infra::repositories::personal_access_tokens_repository;
+use crate::AppState;
+use axum::{extract::State, Extension};
+use chrono::Utc;
+use tracing::log::debug;
+
+pub async fn list_tokens(
+    State(state): State<AppState>,
+    Extension(user_data): Extension<Option<UserData>>,
+) -> Result<Json<ListTokensResponse>, AuthError> {
+    debug!("->> {:<12} - list_tokens", "HANDLER");
+
+    let user_data = user_data.ok_or(AuthError::Unauthenticated)?;
+    user_data.session_id()?;
+
+    let tokens = personal_access_tokens_repository::get_all_by_user(
+        &state.pool,
+        user_data.user_id,
+        Utc::now(),
+    )
+    .await
+    .map_err(AuthError::InfraError)?;
+
+    Ok(Json(ListTokensResponse {
+        tokens: tokens
+            .into_iter()
+            .map(adapt_token_to_token_response)
+            .collect(),
+    }))
+}
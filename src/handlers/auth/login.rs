@@ -1,16 +1,21 @@
+use crate::config::{config, OAuthProviderConfig};
 use crate::domain::models::auth::AuthError;
-use crate::handlers::auth::{get_client, LoginParams, UserData};
+use crate::handlers::auth::providers::{endpoints, get_client};
+use crate::handlers::auth::{validate_return_url, LoginParams, UserData};
+use crate::handlers::extractors::{Path, Query};
 use crate::infra::repositories::{auth_repository, auth_repository::NewOauth2Record};
 use crate::AppState;
 use axum::{
-    extract::{Host, Query, State},
+    extract::{Host, State},
     response::Redirect,
     Extension,
 };
 use oauth2::{CsrfToken, PkceCodeChallenge, Scope};
+use uuid::Uuid;
 
 pub async fn login(
     Extension(user_data): Extension<Option<UserData>>,
+    Path(provider): Path<String>,
     Query(params): Query<LoginParams>,
     State(state): State<AppState>,
     Host(hostname): Host,
@@ -19,22 +24,66 @@ pub async fn login(
         // check if already authenticated
         return Ok(Redirect::to("/"));
     }
-    let client = get_client(hostname)?;
+    let provider = config()
+        .await
+        .oauth_provider(&provider)
+        .ok_or(AuthError::UnknownProvider(provider))?;
+
+    start_login(provider, params, state, hostname, None).await
+}
+
+// Log in with the default provider; this is where `check_auth` sends unauthenticated users
+pub async fn login_default(
+    Extension(user_data): Extension<Option<UserData>>,
+    Query(params): Query<LoginParams>,
+    State(state): State<AppState>,
+    Host(hostname): Host,
+) -> Result<Redirect, AuthError> {
+    if user_data.is_some() {
+        // check if already authenticated
+        return Ok(Redirect::to("/"));
+    }
+    let provider = config()
+        .await
+        .default_oauth_provider()
+        .ok_or("OAuth: no provider configured")?;
+
+    start_login(provider, params, state, hostname, None).await
+}
+
+// Send the user off to the provider. With `link_user_id` the identity they come back with gets
+// linked to that user rather than logged in.
+pub async fn start_login(
+    provider: &OAuthProviderConfig,
+    params: LoginParams,
+    state: AppState,
+    hostname: String,
+    link_user_id: Option<Uuid>,
+) -> Result<Redirect, AuthError> {
+    let return_url = match params.return_url {
+        Some(return_url) => {
+            validate_return_url(&return_url, config().await.return_url_allowed_origins())?
+        }
+        None => "/".to_string(),
+    };
+
+    let endpoints = endpoints(provider).await?;
+    let client = get_client(provider, &endpoints, hostname)?;
 
     let (pkce_code_challenge, pkce_code_verifier) = PkceCodeChallenge::new_random_sha256();
 
     let (authorize_url, csrf_state) = client
         .authorize_url(CsrfToken::new_random)
-        .add_scope(Scope::new(
-            "https://www.googleapis.com/auth/userinfo.email".to_string(),
-        ))
+        .add_scopes(provider.scopes.iter().cloned().map(Scope::new))
         .set_pkce_challenge(pkce_code_challenge)
         .url();
 
     let new_record = NewOauth2Record {
         csrf_state: csrf_state.secret().to_owned(),
         pkce_code_verifier: String::from(pkce_code_verifier.secret()),
-        return_url: params.return_url.unwrap_or_else(|| "/".to_string()),
+        return_url,
+        provider: provider.name.clone(),
+        link_user_id,
     };
 
     auth_repository::insert_oauth2_record(&state.pool, new_record)
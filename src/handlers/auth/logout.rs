@@ -0,0 +1,90 @@
+use crate::config::config;
+use crate::domain::models::auth::AuthError;
+use crate::handlers::auth::providers::endpoints;
+use crate::handlers::auth::{Credential, UserData, CLEAR_SESSION_COOKIE};
+use crate::infra::errors::InfraError;
+use crate::infra::repositories::user_sessions_repository;
+use crate::AppState;
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{AppendHeaders, IntoResponse},
+    Extension,
+};
+use tracing::log::{debug, warn};
+
+pub async fn logout(
+    State(state): State<AppState>,
+    Extension(user_data): Extension<Option<UserData>>,
+) -> Result<impl IntoResponse, AuthError> {
+    debug!("->> {:<12} - logout", "HANDLER");
+
+    // Only a session whose whole token checked out can be ended; knowing the first half of the
+    // token, which is stored as is, isn't enough. Logging out without a session, or with one that
+    // is already gone, still clears the cookie.
+    if let Some(UserData {
+        credential: Credential::Session(session_id),
+        ..
+    }) = user_data
+    {
+        match user_sessions_repository::delete(&state.pool, session_id).await {
+            Ok(user_session) => {
+                if let Some(access_token) = user_session.oauth_access_token {
+                    revoke(user_session.oauth_provider, access_token).await;
+                }
+            }
+            Err(InfraError::NotFound) => {}
+            Err(db_error) => return Err(AuthError::InfraError(db_error)),
+        }
+    }
+
+    let headers = AppendHeaders([(header::SET_COOKIE, CLEAR_SESSION_COOKIE)]);
+
+    Ok((headers, StatusCode::NO_CONTENT))
+}
+
+// Ask the provider to revoke the grant behind a session, as described in RFC 7009. The session
+// is gone either way, so a failure here is only logged.
+async fn revoke(provider: Option<String>, access_token: String) {
+    let config = config().await;
+    if !config.revoke_on_logout() {
+        return;
+    }
+
+    // Sessions from before there was a choice of providers all came from the default one
+    let provider = match provider {
+        Some(provider) => config.oauth_provider(&provider),
+        None => config.default_oauth_provider(),
+    };
+    let Some(provider) = provider else {
+        return;
+    };
+
+    let revocation_url = match endpoints(provider).await {
+        Ok(endpoints) => match endpoints.revocation_url {
+            Some(revocation_url) => revocation_url,
+            None => return,
+        },
+        Err(err) => {
+            warn!("logout: can't revoke the OAuth grant: {:?}", err);
+            return;
+        }
+    };
+
+    // Sent by hand rather than through the oauth2 client, which refuses anything but https and
+    // so can't talk to the local stub
+    let revocation = reqwest::Client::new()
+        .post(revocation_url)
+        .form(&[
+            ("token", access_token.as_str()),
+            ("token_type_hint", "access_token"),
+        ])
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+
+    match revocation {
+        Ok(_) => debug!("->> {:<12} - logout - OAuth grant revoked", "HANDLER"),
+        Err(err) => warn!("logout: failed to revoke the OAuth grant: {}", err),
+    }
+}
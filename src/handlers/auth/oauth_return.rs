@@ -1,32 +1,113 @@
+use crate::config::{config, OAuthProviderConfig};
 use crate::domain::models::auth::AuthError;
-use crate::handlers::auth::get_client;
-use crate::infra::repositories::{auth_repository, user_repository, user_sessions_repository};
+use crate::handlers::auth::providers::{endpoints, fetch_userinfo, get_client, ProviderUserInfo};
+use crate::handlers::auth::{
+    client_ip, digest_secret, session_cookie, user_agent, validate_return_url,
+};
+use crate::handlers::extractors::{Path, Query};
+use crate::infra::errors::InfraError;
+use crate::infra::repositories::{
+    auth_repository, user_identities_repository, user_repository, user_sessions_repository,
+};
 use crate::AppState;
 use axum::{
-    extract::{Host, Query, State},
-    response::{IntoResponse, Redirect},
+    extract::{ConnectInfo, Host, State},
+    http::HeaderMap,
+    response::{IntoResponse, Redirect, Response},
 };
 use chrono::Utc;
 use oauth2::{reqwest::http_client, AuthorizationCode, CsrfToken, PkceCodeVerifier, TokenResponse};
 use std::collections::HashMap;
+use std::net::SocketAddr;
+use tracing::log::{debug, warn};
 use uuid::Uuid;
 
 pub async fn oauth_return(
-    Query(mut params): Query<HashMap<String, String>>,
+    Path(provider): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    State(state): State<AppState>,
+    Host(hostname): Host,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Result<Response, AuthError> {
+    let provider = config()
+        .await
+        .oauth_provider(&provider)
+        .ok_or(AuthError::UnknownProvider(provider))?;
+
+    finish_login(provider, params, state, hostname, peer, headers).await
+}
+
+// Where providers registered before there was a choice of providers still send users back to
+pub async fn oauth_return_legacy(
+    Query(params): Query<HashMap<String, String>>,
     State(state): State<AppState>,
     Host(hostname): Host,
-) -> Result<impl IntoResponse, AuthError> {
-    let state_token = CsrfToken::new(params.remove("state").ok_or("OAuth: without state")?);
-    let code = AuthorizationCode::new(params.remove("code").ok_or("OAuth: without code")?);
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+) -> Result<Response, AuthError> {
+    let provider = config()
+        .await
+        .legacy_oauth_provider()
+        .ok_or("OAuth: no provider configured")?;
+
+    finish_login(provider, params, state, hostname, peer, headers).await
+}
 
-    let (pkce_code_verifier, return_url) =
-        auth_repository::delete_oauth2_record(&state.pool, state_token.secret().to_owned())
-            .await
-            .map_err(AuthError::InfraError)?;
+async fn finish_login(
+    provider: &OAuthProviderConfig,
+    mut params: HashMap<String, String>,
+    state: AppState,
+    hostname: String,
+    peer: SocketAddr,
+    headers: HeaderMap,
+) -> Result<Response, AuthError> {
+    let config = config().await;
 
-    let pkce_code_verifier = PkceCodeVerifier::new(pkce_code_verifier);
+    let state_token = CsrfToken::new(params.remove("state").ok_or(AuthError::MissingOAuthState)?);
 
-    let client = get_client(hostname)?;
+    let oauth2_record = auth_repository::delete_oauth2_record(
+        &state.pool,
+        state_token.secret().to_owned(),
+        provider.name.clone(),
+    )
+    .await
+    .map_err(|db_error| match db_error {
+        InfraError::NotFound => AuthError::UnknownOAuthState,
+        _ => AuthError::InfraError(db_error),
+    })?;
+
+    // Providers report failures, such as the user declining consent, back here instead of a code
+    if let Some(error) = params.remove("error") {
+        return Err(AuthError::ProviderError {
+            error,
+            description: params.remove("error_description"),
+        });
+    }
+
+    // The record is used up either way, but a stale one doesn't get to log anybody in
+    if oauth2_record.created_at < Utc::now() - config.oauth_state_ttl() {
+        return Err(AuthError::ExpiredOAuthState);
+    }
+
+    let code = AuthorizationCode::new(params.remove("code").ok_or(AuthError::MissingOAuthCode)?);
+
+    let pkce_code_verifier = PkceCodeVerifier::new(oauth2_record.pkce_code_verifier);
+    // Records from before return URLs were checked may hold anything, those go home instead
+    let return_url = validate_return_url(
+        &oauth2_record.return_url,
+        config.return_url_allowed_origins(),
+    )
+    .unwrap_or_else(|_| {
+        warn!(
+            "oauth_return: ignoring invalid return_url {:?}",
+            oauth2_record.return_url
+        );
+        "/".to_string()
+    });
+
+    let endpoints = endpoints(provider).await?;
+    let client = get_client(provider, &endpoints, hostname)?;
     let token_response = tokio::task::spawn_blocking(move || {
         client
             .exchange_code(code)
@@ -34,60 +115,162 @@ pub async fn oauth_return(
             .request(http_client)
     })
     .await
-    .map_err(|_| "OAuth: exchange_code failure")?
-    .map_err(|_| "OAuth: tokio spawn blocking failure")?;
-    let access_token = token_response.access_token().secret();
-
-    // Get user info from Google
-    let url =
-        "https://www.googleapis.com/oauth2/v2/userinfo?oauth_token=".to_owned() + access_token;
-    let body = reqwest::get(url)
-        .await
-        .map_err(|_| "OAuth: reqwest failed to query userinfo")?
-        .text()
-        .await
-        .map_err(|_| "OAuth: reqwest received invalid userinfo")?;
-    let mut body: serde_json::Value =
-        serde_json::from_str(body.as_str()).map_err(|_| "OAuth: Serde failed to parse userinfo")?;
-    let email = body["email"]
-        .take()
-        .as_str()
-        .ok_or("OAuth: Serde failed to parse email address")?
-        .to_owned();
-    let verified_email = body["verified_email"]
-        .take()
-        .as_bool()
-        .ok_or("OAuth: Serde failed to parse verified_email")?;
-    if !verified_email {
+    .map_err(|_| "OAuth: tokio spawn blocking failure")?
+    .map_err(|err| AuthError::TokenExchangeFailed(err.to_string()))?;
+    let access_token = token_response.access_token().secret().to_owned();
+
+    let userinfo = fetch_userinfo(provider, &endpoints, &access_token).await?;
+    debug!(
+        "->> {:<12} - oauth_return - {} subject {}",
+        "HANDLER", provider.name, userinfo.subject
+    );
+    if !userinfo.email_verified {
         return Err(AuthError::EmailAddressIsNotVerified);
     }
 
-    let user_id = user_repository::insert_if_not_exists(&state.pool, email)
-        .await
-        .map_err(AuthError::InfraError)?;
+    // A logged-in user came back from linking another identity, they don't need a new session
+    if let Some(link_user_id) = oauth2_record.link_user_id {
+        link_identity(&state, provider, userinfo, link_user_id).await?;
+        return Ok(Redirect::to(return_url.as_str()).into_response());
+    }
+
+    let user_id = find_or_create_user(&state, provider, userinfo).await?;
+
+    let now = Utc::now().timestamp();
+    let lifetime = config
+        .session_idle_timeout()
+        .min(config.session_max_lifetime())
+        .num_seconds();
 
     let session_token_p1 = Uuid::new_v4().to_string();
     let session_token_p2 = Uuid::new_v4().to_string();
-    let session_token = [session_token_p1.as_str(), "_", session_token_p2.as_str()].concat();
-    let headers = axum::response::AppendHeaders([(
+    let cookie_headers = axum::response::AppendHeaders([(
         axum::http::header::SET_COOKIE,
-        "session_token=".to_owned()
-            + &*session_token
-            + "; path=/; httponly; secure; samesite=strict",
+        session_cookie(&session_token_p1, &session_token_p2, lifetime),
     )]);
-    let now = Utc::now().timestamp();
 
     let new_user_session = user_sessions_repository::NewUserSessionDb {
         user_id,
         session_token_p1,
-        session_token_p2,
+        session_token_p2_digest: digest_secret(&session_token_p2),
         created_at: now,
-        expires_at: now + 60 * 60 * 24,
+        expires_at: now + lifetime,
+        // Only needed, and so only kept, when the grant gets revoked on logout
+        oauth_access_token: config.revoke_on_logout().then_some(access_token),
+        ip_address: Some(client_ip(&headers, peer).await),
+        user_agent: user_agent(&headers),
+        last_seen_at: now,
+        oauth_provider: Some(provider.name.clone()),
     };
 
     user_sessions_repository::insert(&state.pool, new_user_session)
         .await
         .map_err(AuthError::InfraError)?;
 
-    Ok((headers, Redirect::to(return_url.as_str())))
+    Ok((cookie_headers, Redirect::to(return_url.as_str())).into_response())
+}
+
+// The provider users were created with before identities existed
+const LEGACY_PROVIDER: &str = "google";
+
+// The user an identity belongs to. Users from before identities existed are matched by email
+// once and get the identity attached, but only when the login comes from the provider they signed
+// up with; beyond that an email address never merges accounts.
+async fn find_or_create_user(
+    state: &AppState,
+    provider: &OAuthProviderConfig,
+    userinfo: ProviderUserInfo,
+) -> Result<Uuid, AuthError> {
+    match user_identities_repository::get_by_subject(
+        &state.pool,
+        provider.name.clone(),
+        userinfo.subject.clone(),
+    )
+    .await
+    {
+        Ok(identity) => {
+            if identity.email != userinfo.email {
+                user_identities_repository::update_email(&state.pool, identity.id, userinfo.email)
+                    .await
+                    .map_err(AuthError::InfraError)?;
+            }
+            return Ok(identity.user_id);
+        }
+        Err(InfraError::NotFound) => {}
+        Err(db_error) => return Err(AuthError::InfraError(db_error)),
+    }
+
+    match user_repository::get_by_email(&state.pool, userinfo.email.clone()).await {
+        Ok(user) => {
+            let identities = user_identities_repository::count_by_user(&state.pool, user.id)
+                .await
+                .map_err(AuthError::InfraError)?;
+            if identities > 0 || provider.name != LEGACY_PROVIDER {
+                return Err(AuthError::EmailTaken);
+            }
+
+            let new_identity = user_identities_repository::NewUserIdentityDb {
+                user_id: user.id,
+                provider: provider.name.clone(),
+                subject: userinfo.subject,
+                email: userinfo.email,
+            };
+            user_identities_repository::insert(&state.pool, new_identity)
+                .await
+                .map_err(AuthError::InfraError)?;
+
+            Ok(user.id)
+        }
+        Err(InfraError::NotFound) => user_identities_repository::insert_with_user(
+            &state.pool,
+            provider.name.clone(),
+            userinfo.subject,
+            userinfo.email,
+        )
+        .await
+        .map_err(AuthError::InfraError),
+        Err(db_error) => Err(AuthError::InfraError(db_error)),
+    }
+}
+
+async fn link_identity(
+    state: &AppState,
+    provider: &OAuthProviderConfig,
+    userinfo: ProviderUserInfo,
+    user_id: Uuid,
+) -> Result<(), AuthError> {
+    match user_identities_repository::get_by_subject(
+        &state.pool,
+        provider.name.clone(),
+        userinfo.subject.clone(),
+    )
+    .await
+    {
+        Ok(identity) if identity.user_id == user_id => return Ok(()),
+        Ok(_) => return Err(AuthError::IdentityLinkedToAnotherUser),
+        Err(InfraError::NotFound) => {}
+        Err(db_error) => return Err(AuthError::InfraError(db_error)),
+    }
+
+    let identities = user_identities_repository::get_all_by_user(&state.pool, user_id)
+        .await
+        .map_err(AuthError::InfraError)?;
+    if identities
+        .iter()
+        .any(|identity| identity.provider == provider.name)
+    {
+        return Err(AuthError::ProviderAlreadyLinked(provider.name.clone()));
+    }
+
+    let new_identity = user_identities_repository::NewUserIdentityDb {
+        user_id,
+        provider: provider.name.clone(),
+        subject: userinfo.subject,
+        email: userinfo.email,
+    };
+    user_identities_repository::insert(&state.pool, new_identity)
+        .await
+        .map_err(AuthError::InfraError)?;
+
+    Ok(())
 }
@@ -0,0 +1,256 @@
+use crate::config::{OAuthProviderConfig, OAuthProviderKind};
+use crate::domain::models::auth::AuthError;
+use oauth2::{
+    basic::BasicClient, AuthUrl, ClientId, ClientSecret, RedirectUrl, RevocationUrl, TokenUrl,
+};
+use reqwest::header;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+// GitHub turns away API requests without a user agent
+const USER_AGENT: &str = "axum-diesel-practice";
+
+// Endpoints discovered for OpenID Connect providers, by provider name
+static DISCOVERED: OnceLock<Mutex<HashMap<String, ProviderEndpoints>>> = OnceLock::new();
+
+#[derive(Clone, Debug)]
+pub struct ProviderEndpoints {
+    pub auth_url: String,
+    pub token_url: String,
+    pub userinfo_url: String,
+    pub revocation_url: Option<String>,
+}
+
+// Who a provider says the user is, whatever shape its userinfo has
+#[derive(Debug)]
+pub struct ProviderUserInfo {
+    pub subject: String,
+    pub email: String,
+    pub email_verified: bool,
+}
+
+#[derive(Deserialize)]
+struct OidcDiscoveryDocument {
+    authorization_endpoint: String,
+    token_endpoint: String,
+    userinfo_endpoint: Option<String>,
+    revocation_endpoint: Option<String>,
+}
+
+// The endpoints of a provider, from its configuration or, for whatever is missing there, from the
+// discovery document of its issuer, which is fetched once and then remembered
+pub async fn endpoints(provider: &OAuthProviderConfig) -> Result<ProviderEndpoints, AuthError> {
+    if let (Some(auth_url), Some(token_url), Some(userinfo_url)) = (
+        &provider.auth_url,
+        &provider.token_url,
+        &provider.userinfo_url,
+    ) {
+        return Ok(ProviderEndpoints {
+            auth_url: auth_url.clone(),
+            token_url: token_url.clone(),
+            userinfo_url: userinfo_url.clone(),
+            revocation_url: provider.revocation_url.clone(),
+        });
+    }
+
+    let discovered = DISCOVERED.get_or_init(Default::default);
+    if let Some(endpoints) = discovered.lock().unwrap().get(&provider.name) {
+        return Ok(endpoints.clone());
+    }
+
+    let endpoints = discover(provider).await?;
+    discovered
+        .lock()
+        .unwrap()
+        .insert(provider.name.clone(), endpoints.clone());
+
+    Ok(endpoints)
+}
+
+async fn discover(provider: &OAuthProviderConfig) -> Result<ProviderEndpoints, AuthError> {
+    let issuer_url = provider
+        .issuer_url
+        .as_deref()
+        .ok_or("OAuth: provider has neither endpoints nor an issuer to discover them from")?;
+    let url = format!(
+        "{}/.well-known/openid-configuration",
+        issuer_url.trim_end_matches('/')
+    );
+
+    let body = reqwest::Client::new()
+        .get(url)
+        .header(header::USER_AGENT, USER_AGENT)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|_| "OAuth: reqwest failed to fetch the discovery document")?
+        .text()
+        .await
+        .map_err(|_| "OAuth: reqwest received an invalid discovery document")?;
+    let document: OidcDiscoveryDocument = serde_json::from_str(body.as_str())
+        .map_err(|_| "OAuth: Serde failed to parse the discovery document")?;
+
+    // Endpoints that are configured win over discovered ones
+    Ok(ProviderEndpoints {
+        auth_url: provider
+            .auth_url
+            .clone()
+            .unwrap_or(document.authorization_endpoint),
+        token_url: provider
+            .token_url
+            .clone()
+            .unwrap_or(document.token_endpoint),
+        userinfo_url: provider
+            .userinfo_url
+            .clone()
+            .or(document.userinfo_endpoint)
+            .ok_or("OAuth: provider has no userinfo endpoint")?,
+        revocation_url: provider
+            .revocation_url
+            .clone()
+            .or(document.revocation_endpoint),
+    })
+}
+
+pub fn get_client(
+    provider: &OAuthProviderConfig,
+    endpoints: &ProviderEndpoints,
+    hostname: String,
+) -> Result<BasicClient, AuthError> {
+    let client_id = ClientId::new(
+        provider
+            .client_id
+            .clone()
+            .ok_or("OAuth: provider has no client id")?,
+    );
+    let client_secret = provider.client_secret.clone().map(ClientSecret::new);
+    let auth_url = AuthUrl::new(endpoints.auth_url.clone())
+        .map_err(|_| "OAuth: invalid authorization endpoint URL")?;
+    let token_url = TokenUrl::new(endpoints.token_url.clone())
+        .map_err(|_| "OAuth: invalid token endpoint URL")?;
+
+    let protocol = if hostname.starts_with("localhost") || hostname.starts_with("127.0.0.1") {
+        "http"
+    } else {
+        "https"
+    };
+
+    // Clients registered before there was a choice of providers know only the old redirect URI
+    let redirect_url = if provider.legacy_redirect {
+        format!("{}://{}/api/auth/oauth_return", protocol, hostname)
+    } else {
+        format!(
+            "{}://{}/api/auth/oauth_return/{}",
+            protocol, hostname, provider.name
+        )
+    };
+
+    // Set up the config for the OAuth2 process with this provider
+    let client = BasicClient::new(client_id, client_secret, auth_url, Some(token_url))
+        .set_redirect_uri(
+            RedirectUrl::new(redirect_url).map_err(|_| "OAuth: invalid redirect URL")?,
+        );
+
+    match &endpoints.revocation_url {
+        Some(revocation_url) => Ok(client.set_revocation_uri(
+            RevocationUrl::new(revocation_url.clone())
+                .map_err(|_| "OAuth: invalid revocation endpoint URL")?,
+        )),
+        None => Ok(client),
+    }
+}
+
+// Ask the provider who the access token belongs to and map its answer, each kind of provider
+// having its own idea of what userinfo looks like
+pub async fn fetch_userinfo(
+    provider: &OAuthProviderConfig,
+    endpoints: &ProviderEndpoints,
+    access_token: &str,
+) -> Result<ProviderUserInfo, AuthError> {
+    query_userinfo(provider, endpoints, access_token)
+        .await
+        .map_err(|err| AuthError::UserinfoFailed(err.to_string()))
+}
+
+async fn query_userinfo(
+    provider: &OAuthProviderConfig,
+    endpoints: &ProviderEndpoints,
+    access_token: &str,
+) -> Result<ProviderUserInfo, &'static str> {
+    let body = get_json(&endpoints.userinfo_url, access_token).await?;
+
+    match provider.kind {
+        OAuthProviderKind::Google => Ok(ProviderUserInfo {
+            subject: claim_string(&body["id"]).ok_or("OAuth: Serde failed to parse id")?,
+            email: claim_string(&body["email"])
+                .ok_or("OAuth: Serde failed to parse email address")?,
+            email_verified: body["verified_email"]
+                .as_bool()
+                .ok_or("OAuth: Serde failed to parse verified_email")?,
+        }),
+        // The profile may not show an email address at all, the address list always does
+        OAuthProviderKind::GitHub => {
+            let subject = claim_string(&body["id"]).ok_or("OAuth: Serde failed to parse id")?;
+
+            let emails_url = provider
+                .emails_url
+                .as_deref()
+                .ok_or("OAuth: provider has no emails endpoint")?;
+            let emails = get_json(emails_url, access_token).await?;
+            let primary = emails
+                .as_array()
+                .and_then(|emails| {
+                    emails
+                        .iter()
+                        .find(|email| email["primary"].as_bool() == Some(true))
+                })
+                .ok_or("OAuth: Serde failed to find the primary email address")?;
+
+            Ok(ProviderUserInfo {
+                subject,
+                email: claim_string(&primary["email"])
+                    .ok_or("OAuth: Serde failed to parse email address")?,
+                email_verified: primary["verified"].as_bool().unwrap_or(false),
+            })
+        }
+        OAuthProviderKind::GitLab | OAuthProviderKind::Oidc => Ok(ProviderUserInfo {
+            subject: claim_string(&body["sub"]).ok_or("OAuth: Serde failed to parse sub")?,
+            email: claim_string(&body["email"])
+                .ok_or("OAuth: Serde failed to parse email address")?,
+            // Some providers send booleans as strings
+            email_verified: match &body["email_verified"] {
+                Value::Bool(verified) => *verified,
+                Value::String(verified) => verified == "true",
+                _ => false,
+            },
+        }),
+    }
+}
+
+async fn get_json(url: &str, access_token: &str) -> Result<Value, &'static str> {
+    let body = reqwest::Client::new()
+        .get(url)
+        .bearer_auth(access_token)
+        .header(header::USER_AGENT, USER_AGENT)
+        .header(header::ACCEPT, "application/json")
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|_| "OAuth: reqwest failed to query userinfo")?
+        .text()
+        .await
+        .map_err(|_| "OAuth: reqwest received invalid userinfo")?;
+
+    serde_json::from_str(body.as_str()).map_err(|_| "OAuth: Serde failed to parse userinfo")
+}
+
+// Identifiers come as strings from some providers and as numbers from others
+fn claim_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
@@ -0,0 +1,24 @@
+use axum::{http::StatusCode, Form};
+use std::collections::HashMap;
+use tracing::log::{debug, info};
+
+// Stand-in for the provider's revocation endpoint, only routed when OAUTH_REVOCATION_STUB is set.
+// Point OAUTH_<PROVIDER>_REVOCATION_URL at it to see logout revoke grants without a real provider.
+pub async fn revocation_stub(Form(params): Form<HashMap<String, String>>) -> StatusCode {
+    debug!("->> {:<12} - revocation_stub", "HANDLER");
+
+    match params.get("token") {
+        Some(token) => {
+            info!(
+                "revocation_stub: revoked {} of {} characters",
+                params
+                    .get("token_type_hint")
+                    .map(String::as_str)
+                    .unwrap_or("token"),
+                token.len()
+            );
+            StatusCode::OK
+        }
+        None => StatusCode::BAD_REQUEST,
+    }
+}
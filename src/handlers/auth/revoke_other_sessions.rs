@@ -0,0 +1,28 @@
+use crate::domain::models::auth::AuthError;
+use crate::handlers::auth::{RevokeOtherSessionsResponse, UserData};
+use crate::handlers::extractors::Json;
+use crate::infra::repositories::user_sessions_repository;
+use crate::AppState;
+use axum::{extract::State, Extension};
+use tracing::log::debug;
+
+// Log out everywhere else: every session of the caller but the one making the request
+pub async fn revoke_other_sessions(
+    State(state): State<AppState>,
+    Extension(user_data): Extension<Option<UserData>>,
+) -> Result<Json<RevokeOtherSessionsResponse>, AuthError> {
+    debug!("->> {:<12} - revoke_other_sessions", "HANDLER");
+
+    let user_data = user_data.ok_or(AuthError::Unauthenticated)?;
+    let session_id = user_data.session_id()?;
+
+    let revoked = user_sessions_repository::delete_all_by_user_except(
+        &state.pool,
+        user_data.user_id,
+        session_id,
+    )
+    .await
+    .map_err(AuthError::InfraError)?;
+
+    Ok(Json(RevokeOtherSessionsResponse { revoked }))
+}
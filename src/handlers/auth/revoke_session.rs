@@ -0,0 +1,43 @@
+use crate::domain::models::auth::AuthError;
+use crate::handlers::auth::{UserData, CLEAR_SESSION_COOKIE};
+use crate::handlers::extractors::Path;
+use crate::infra::errors::InfraError;
+use crate::infra::repositories::user_sessions_repository;
+use crate::AppState;
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Extension,
+};
+use tracing::log::debug;
+use uuid::Uuid;
+
+pub async fn revoke_session(
+    State(state): State<AppState>,
+    Extension(user_data): Extension<Option<UserData>>,
+    Path(id): Path<Uuid>,
+) -> Result<Response, AuthError> {
+    debug!("->> {:<12} - revoke_session", "HANDLER");
+
+    let user_data = user_data.ok_or(AuthError::Unauthenticated)?;
+    let session_id = user_data.session_id()?;
+
+    user_sessions_repository::delete_by_user(&state.pool, id, user_data.user_id)
+        .await
+        .map_err(|db_error| match db_error {
+            InfraError::NotFound => AuthError::SessionNotFound(id),
+            _ => AuthError::InfraError(db_error),
+        })?;
+
+    // Revoking the session in use is logging out, so the cookie goes too
+    if id == session_id {
+        return Ok((
+            [(header::SET_COOKIE, CLEAR_SESSION_COOKIE)],
+            StatusCode::NO_CONTENT,
+        )
+            .into_response());
+    }
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
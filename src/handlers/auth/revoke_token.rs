@@ -0,0 +1,29 @@
+use crate::domain::models::auth::AuthError;
+use crate::handlers::auth::UserData;
+use crate::handlers::extractors::Path;
+use crate::infra::errors::InfraError;
+use crate::infra::repositories::personal_access_tokens_repository;
+use crate::AppState;
+use axum::{extract::State, http::StatusCode, Extension};
+use tracing::log::debug;
+use uuid::Uuid;
+
+pub async fn revoke_token(
+    State(state): State<AppState>,
+    Extension(user_data): Extension<Option<UserData>>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AuthError> {
+    debug!("->> {:<12} - revoke_token", "HANDLER");
+
+    let user_data = user_data.ok_or(AuthError::Unauthenticated)?;
+    user_data.session_id()?;
+
+    personal_access_tokens_repository::delete_by_user(&state.pool, id, user_data.user_id)
+        .await
+        .map_err(|db_error| match db_error {
+            InfraError::NotFound => AuthError::TokenNotFound(id),
+            _ => AuthError::InfraError(db_error),
+        })?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,35 @@
+use crate::domain::models::auth::AuthError;
+use crate::handlers::auth::UserData;
+use crate::handlers::extractors::Path;
+use crate::infra::errors::InfraError;
+use crate::infra::repositories::user_identities_repository;
+use crate::AppState;
+use axum::{extract::State, http::StatusCode, Extension};
+use tracing::log::debug;
+use uuid::Uuid;
+
+pub async fn unlink_identity(
+    State(state): State<AppState>,
+    Extension(user_data): Extension<Option<UserData>>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AuthError> {
+    debug!("->> {:<12} - unlink_identity", "HANDLER");
+
+    let user_data = user_data.ok_or(AuthError::Unauthenticated)?;
+    user_data.session_id()?;
+
+    // Without an identity left the user couldn't log in anymore
+    let unlinked =
+        user_identities_repository::delete_unless_last(&state.pool, id, user_data.user_id)
+            .await
+            .map_err(|db_error| match db_error {
+                InfraError::NotFound => AuthError::IdentityNotFound(id),
+                _ => AuthError::InfraError(db_error),
+            })?;
+
+    if !unlinked {
+        return Err(AuthError::LastIdentity);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
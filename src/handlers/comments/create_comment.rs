@@ -0,0 +1,44 @@
+use crate::domain::models::comment::CommentError;
+use crate::handlers::auth::UserData;
+use crate::handlers::comments::{
+    adapt_comment_to_comment_response, check_post_exists, normalize_body, CommentResponse,
+    CreateCommentRequest,
+};
+use crate::handlers::extractors::{Json, Path};
+use crate::infra::errors::InfraError;
+use crate::infra::repositories::comment_repository;
+use crate::AppState;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Extension;
+use tracing::log::debug;
+use uuid::Uuid;
+
+pub async fn create_comment(
+    State(state): State<AppState>,
+    Extension(user_data): Extension<Option<UserData>>,
+    Path(post_id): Path<Uuid>,
+    Json(new_comment): Json<CreateCommentRequest>,
+) -> Result<(StatusCode, Json<CommentResponse>), CommentError> {
+    debug!("->> {:<12} - create_comment", "HANDLER");
+
+    let user_data = user_data.ok_or(CommentError::Unauthenticated)?;
+
+    check_post_exists(&state, post_id).await?;
+
+    let body = normalize_body(new_comment.body)?;
+    let parent_id = new_comment.parent_id;
+
+    let created_comment =
+        comment_repository::insert(&state.pool, post_id, user_data.user_id, parent_id, body)
+            .await
+            .map_err(|db_error| match (db_error, parent_id) {
+                (InfraError::NotFound, Some(parent_id)) => CommentError::ParentNotFound(parent_id),
+                (db_error, _) => CommentError::InfraError(db_error),
+            })?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(adapt_comment_to_comment_response(created_comment)),
+    ))
+}
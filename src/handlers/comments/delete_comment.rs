@@ -0,0 +1,38 @@
+use crate::domain::models::comment::CommentError;
+use crate::handlers::auth::UserData;
+use crate::handlers::comments::{adapt_comment_to_comment_response, missing_comment_error};
+use crate::handlers::extractors::{Json, Path};
+use crate::infra::errors::InfraError;
+use crate::infra::repositories::comment_repository;
+use crate::AppState;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Extension;
+use tracing::log::debug;
+use uuid::Uuid;
+
+pub async fn delete_comment(
+    State(state): State<AppState>,
+    Extension(user_data): Extension<Option<UserData>>,
+    Path((post_id, id)): Path<(Uuid, Uuid)>,
+) -> Result<Response, CommentError> {
+    debug!("->> {:<12} - delete_comment", "HANDLER");
+
+    let user_data = user_data.ok_or(CommentError::Unauthenticated)?;
+
+    // Besides its author, the author of the post can moderate the comments on it
+    let tombstone = match comment_repository::delete(&state.pool, post_id, id, user_data.user_id)
+        .await
+    {
+        Ok(tombstone) => tombstone,
+        Err(InfraError::NotFound) => return Err(missing_comment_error(&state, post_id, id).await),
+        Err(db_error) => return Err(CommentError::InfraError(db_error)),
+    };
+
+    // A comment with replies stays in the thread as a tombstone, anything else is simply gone
+    Ok(match tombstone {
+        Some(comment) => Json(adapt_comment_to_comment_response(comment)).into_response(),
+        None => StatusCode::NO_CONTENT.into_response(),
+    })
+}
@@ -0,0 +1,27 @@
+use crate::domain::models::comment::CommentError;
+use crate::handlers::comments::{build_comment_tree, check_post_exists, ListCommentsResponse};
+use crate::handlers::extractors::{Json, Path, Query};
+use crate::infra::repositories::comment_repository::{self, CommentsFilter};
+use crate::AppState;
+use axum::extract::State;
+use tracing::log::debug;
+use uuid::Uuid;
+
+pub async fn list_comments(
+    State(state): State<AppState>,
+    Path(post_id): Path<Uuid>,
+    Query(params): Query<CommentsFilter>,
+) -> Result<Json<ListCommentsResponse>, CommentError> {
+    debug!("->> {:<12} - list_comments", "HANDLER");
+
+    check_post_exists(&state, post_id).await?;
+
+    let page = comment_repository::get_all(&state.pool, post_id, params)
+        .await
+        .map_err(CommentError::InfraError)?;
+
+    Ok(Json(ListCommentsResponse {
+        comments: build_comment_tree(page.comments),
+        next_cursor: page.next_cursor,
+    }))
+}
@@ -0,0 +1,135 @@
+use crate::domain::models::comment::{CommentError, CommentModel};
+use crate::infra::errors::InfraError;
+use crate::infra::repositories::{comment_repository, post_repository};
+use crate::AppState;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+const DELETED_COMMENT_BODY: &str = "[deleted]";
+
+pub mod create_comment;
+pub mod delete_comment;
+pub mod list_comments;
+pub mod update_comment;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommentResponse {
+    id: Uuid,
+    post_id: Uuid,
+    author_id: Option<Uuid>,
+    author_email: Option<String>,
+    parent_id: Option<Uuid>,
+    body: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    deleted: bool,
+    replies: Vec<CommentResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateCommentRequest {
+    body: String,
+    parent_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateCommentRequest {
+    body: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListCommentsResponse {
+    comments: Vec<CommentResponse>,
+    next_cursor: Option<String>,
+}
+
+// Helper function to adapt a single CommentModel to a CommentResponse without its replies.
+// Tombstones keep their place in the thread but say nothing about who wrote what.
+pub fn adapt_comment_to_comment_response(comment: CommentModel) -> CommentResponse {
+    let deleted = comment.deleted_at.is_some();
+
+    CommentResponse {
+        id: comment.id,
+        post_id: comment.post_id,
+        author_id: if deleted { None } else { comment.author_id },
+        author_email: if deleted { None } else { comment.author_email },
+        parent_id: comment.parent_id,
+        body: if deleted {
+            String::from(DELETED_COMMENT_BODY)
+        } else {
+            comment.body
+        },
+        created_at: comment.created_at,
+        updated_at: comment.updated_at,
+        deleted,
+        replies: Vec::new(),
+    }
+}
+
+// Nest a flat list of comments, ordered oldest first, into trees of replies
+pub fn build_comment_tree(comments: Vec<CommentModel>) -> Vec<CommentResponse> {
+    let mut children: HashMap<Option<Uuid>, Vec<CommentResponse>> = HashMap::new();
+
+    for comment in comments {
+        let parent_id = comment.parent_id;
+        children
+            .entry(parent_id)
+            .or_default()
+            .push(adapt_comment_to_comment_response(comment));
+    }
+
+    let mut roots = children.remove(&None).unwrap_or_default();
+    for root in roots.iter_mut() {
+        attach_replies(root, &mut children);
+    }
+
+    roots
+}
+
+fn attach_replies(
+    comment: &mut CommentResponse,
+    children: &mut HashMap<Option<Uuid>, Vec<CommentResponse>>,
+) {
+    comment.replies = children.remove(&Some(comment.id)).unwrap_or_default();
+    for reply in comment.replies.iter_mut() {
+        attach_replies(reply, children);
+    }
+}
+
+// Comments only exist on posts that exist, i.e. that aren't in the trash
+pub async fn check_post_exists(state: &AppState, post_id: Uuid) -> Result<(), CommentError> {
+    post_repository::get(&state.pool, post_id)
+        .await
+        .map_err(|db_error| match db_error {
+            InfraError::NotFound => CommentError::PostNotFound(post_id),
+            _ => CommentError::InfraError(db_error),
+        })?;
+
+    Ok(())
+}
+
+// Writes on behalf of a user only change the comments they may change, so when nothing was
+// changed, look up whether the post or the comment is missing, or the comment isn't theirs
+pub async fn missing_comment_error(state: &AppState, post_id: Uuid, id: Uuid) -> CommentError {
+    if let Err(comment_error) = check_post_exists(state, post_id).await {
+        return comment_error;
+    }
+
+    match comment_repository::get(&state.pool, post_id, id).await {
+        Ok(_) => CommentError::Forbidden(id),
+        Err(InfraError::NotFound) => CommentError::NotFound(id),
+        Err(db_error) => CommentError::InfraError(db_error),
+    }
+}
+
+pub fn normalize_body(body: String) -> Result<String, CommentError> {
+    let body = body.trim();
+
+    if body.is_empty() {
+        return Err(CommentError::EmptyBody);
+    }
+
+    Ok(body.to_string())
+}
@@ -0,0 +1,38 @@
+use crate::domain::models::comment::CommentError;
+use crate::handlers::auth::UserData;
+use crate::handlers::comments::{
+    adapt_comment_to_comment_response, missing_comment_error, normalize_body, CommentResponse,
+    UpdateCommentRequest,
+};
+use crate::handlers::extractors::{Json, Path};
+use crate::infra::errors::InfraError;
+use crate::infra::repositories::comment_repository;
+use crate::AppState;
+use axum::extract::State;
+use axum::Extension;
+use tracing::log::debug;
+use uuid::Uuid;
+
+pub async fn update_comment(
+    State(state): State<AppState>,
+    Extension(user_data): Extension<Option<UserData>>,
+    Path((post_id, id)): Path<(Uuid, Uuid)>,
+    Json(updated_comment): Json<UpdateCommentRequest>,
+) -> Result<Json<CommentResponse>, CommentError> {
+    debug!("->> {:<12} - update_comment", "HANDLER");
+
+    let user_data = user_data.ok_or(CommentError::Unauthenticated)?;
+
+    let body = normalize_body(updated_comment.body)?;
+
+    // Nobody gets to put words in someone else's mouth, not even the post author
+    let updated =
+        comment_repository::update(&state.pool, post_id, id, body, user_data.user_id).await;
+    let updated_comment = match updated {
+        Ok(comment) => comment,
+        Err(InfraError::NotFound) => return Err(missing_comment_error(&state, post_id, id).await),
+        Err(db_error) => return Err(CommentError::InfraError(db_error)),
+    };
+
+    Ok(Json(adapt_comment_to_comment_response(updated_comment)))
+}
@@ -0,0 +1,187 @@
+use crate::config::config;
+use crate::domain::problem::{FieldError, Problem};
+use crate::handlers::validation::{Validate, Validator};
+use axum::{
+    async_trait,
+    extract::{
+        path::ErrorKind,
+        rejection::{JsonRejection, PathRejection, QueryRejection},
+        FromRequest, FromRequestParts, Request,
+    },
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::error::Error;
+use tracing::log::debug;
+
+// Drop-in replacements for axum's `Json`, `Path` and `Query` whose rejections are problems like
+// every other error, instead of plain text
+
+pub struct Json<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for Json<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Problem;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::Json::<T>::from_request(req, state).await {
+            Ok(axum::Json(value)) => Ok(Self(value)),
+            Err(rejection) => Err(adapt_json_rejection(rejection)),
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        axum::Json(self.0).into_response()
+    }
+}
+
+// A JSON body that also keeps to the rules of its type. Everything that is wrong with it is
+// reported in one 422.
+pub struct ValidJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = Problem;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let config = config().await;
+        let Json(value) = Json::<T>::from_request(req, state).await?;
+
+        let mut validator = Validator::default();
+        value.validate(config, &mut validator);
+
+        let errors = validator.into_errors();
+        if !errors.is_empty() {
+            debug!("ValidJson rejected: {} invalid fields", errors.len());
+            return Err(Problem::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "validation_failed",
+                "The request has invalid fields",
+            )
+            .with_errors(errors));
+        }
+
+        Ok(Self(value))
+    }
+}
+
+pub struct Path<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for Path<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = Problem;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::extract::Path::<T>::from_request_parts(parts, state).await {
+            Ok(axum::extract::Path(value)) => Ok(Self(value)),
+            Err(rejection) => Err(adapt_path_rejection(rejection)),
+        }
+    }
+}
+
+pub struct Query<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for Query<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Problem;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::extract::Query::<T>::from_request_parts(parts, state).await {
+            Ok(axum::extract::Query(value)) => Ok(Self(value)),
+            Err(rejection) => Err(adapt_query_rejection(rejection)),
+        }
+    }
+}
+
+fn adapt_json_rejection(rejection: JsonRejection) -> Problem {
+    debug!("Json rejected: {}", rejection.body_text());
+
+    let status = rejection.status();
+    match rejection {
+        // The body is JSON but doesn't fit, so say which field is wrong
+        JsonRejection::JsonDataError(err) => {
+            let errors = json_field_error(&err).into_iter().collect();
+            Problem::new(status, "invalid_body", err.body_text()).with_errors(errors)
+        }
+        JsonRejection::JsonSyntaxError(err) => {
+            Problem::new(status, "malformed_json", err.body_text())
+        }
+        JsonRejection::MissingJsonContentType(err) => {
+            Problem::new(status, "unsupported_media_type", err.body_text())
+        }
+        rejection if status == StatusCode::PAYLOAD_TOO_LARGE => {
+            Problem::new(status, "payload_too_large", rejection.body_text())
+        }
+        rejection => Problem::new(status, "invalid_body", rejection.body_text()),
+    }
+}
+
+// axum deserializes with serde_path_to_error, which knows where in the body it failed
+fn json_field_error(err: &(dyn Error + 'static)) -> Option<FieldError> {
+    let mut source = Some(err);
+    while let Some(err) = source {
+        if let Some(err) = err.downcast_ref::<serde_path_to_error::Error<serde_json::Error>>() {
+            return Some(FieldError {
+                field: err.path().to_string(),
+                message: err.inner().to_string(),
+            });
+        }
+        source = err.source();
+    }
+    None
+}
+
+fn adapt_path_rejection(rejection: PathRejection) -> Problem {
+    debug!("Path rejected: {}", rejection.body_text());
+
+    let status = rejection.status();
+    // Routes and their extractors not matching up is on us, not the client
+    if status.is_server_error() {
+        return Problem::new(status, "internal_error", "Internal server error");
+    }
+
+    match rejection {
+        PathRejection::FailedToDeserializePathParams(err) => {
+            let errors = match err.kind() {
+                ErrorKind::ParseErrorAtKey {
+                    key, expected_type, ..
+                } => vec![FieldError {
+                    field: key.clone(),
+                    message: format!("expected {}", expected_type),
+                }],
+                ErrorKind::InvalidUtf8InPathParam { key } => vec![FieldError {
+                    field: key.clone(),
+                    message: String::from("invalid UTF-8"),
+                }],
+                _ => Vec::new(),
+            };
+            Problem::new(status, "invalid_path", err.body_text()).with_errors(errors)
+        }
+        rejection => Problem::new(status, "invalid_path", rejection.body_text()),
+    }
+}
+
+fn adapt_query_rejection(rejection: QueryRejection) -> Problem {
+    debug!("Query rejected: {}", rejection.body_text());
+
+    Problem::new(rejection.status(), "invalid_query", rejection.body_text())
+}
@@ -1,2 +1,7 @@
 pub mod auth;
+pub mod comments;
+pub mod extractors;
 pub mod posts;
+pub mod tags;
+pub mod users;
+pub mod validation;
@@ -0,0 +1,31 @@
+use crate::domain::models::post::PostError;
+use crate::handlers::extractors::{Json, Path};
+use crate::handlers::posts::{adapt_post_to_post_response, missing_post_error, PostResponse};
+use crate::infra::errors::InfraError;
+use crate::infra::repositories::post_repository;
+use crate::middlewares::{EditAnyPost, PostAuthor};
+use crate::AppState;
+use axum::extract::State;
+use tracing::log::debug;
+use uuid::Uuid;
+
+pub async fn cancel_schedule(
+    State(state): State<AppState>,
+    author: PostAuthor<EditAnyPost>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<PostResponse>, PostError> {
+    debug!("->> {:<12} - cancel_schedule", "HANDLER");
+
+    let author_id = author.author_filter();
+    let draft = match post_repository::schedule(&state.pool, id, None, author_id).await {
+        Ok(post) => post,
+        // A post that is there and may be changed can only be live already
+        Err(InfraError::NotFound) => {
+            let otherwise = PostError::AlreadyPublished(id);
+            return Err(missing_post_error(&state, id, author_id, otherwise).await);
+        }
+        Err(db_error) => return Err(PostError::InfraError(db_error)),
+    };
+
+    Ok(Json(adapt_post_to_post_response(draft)))
+}
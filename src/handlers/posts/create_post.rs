@@ -1,34 +1,73 @@
 use crate::domain::models::post::PostError;
-use crate::handlers::posts::{CreatePostRequest, PostResponse};
+use crate::handlers::extractors::{Json, ValidJson};
+use crate::handlers::posts::{
+    adapt_post_to_post_response, claim_slug, normalize_tags, slugify, CreatePostRequest,
+    PostResponse,
+};
+use crate::infra::errors::InfraError;
 use crate::infra::repositories::post_repository;
+use crate::middlewares::{Authorized, CreatePosts};
 use crate::AppState;
-use axum::{extract::State, Json};
+use axum::extract::State;
 use tracing::log::debug;
 
+// Tries at inserting a post whose generated slug concurrent posts keep taking
+const SLUG_ATTEMPTS: u32 = 3;
+
 pub async fn create_post(
     State(state): State<AppState>,
-    Json(new_post): Json<CreatePostRequest>,
+    Authorized(user_data, _): Authorized<CreatePosts>,
+    ValidJson(new_post): ValidJson<CreatePostRequest>,
 ) -> Result<Json<PostResponse>, PostError> {
     debug!("->> {:<12} - create_post", "HANDLER");
 
+    let title = new_post.title.trim().to_string();
+
+    // An explicit slug must be free; a generated one gets a suffix when it isn't
+    let explicit_slug = new_post.slug.is_some();
+    let slug = match new_post.slug {
+        Some(requested) => claim_slug(&state, &requested, None).await?,
+        None => match slugify(&title) {
+            slug if slug.is_empty() => String::from("post"),
+            slug => slug,
+        },
+    };
+
+    let tags = normalize_tags(new_post.tags.unwrap_or_default())?;
+
     let new_post_db = post_repository::NewPostDb {
-        title: new_post.title,
+        title,
         body: new_post.body,
         published: false,
+        author_id: user_data.user_id,
+        slug,
     };
 
-    // Insert the new post into the database using the repository
-    let created_post = post_repository::insert(&state.pool, new_post_db)
+    // Insert the new post into the database using the repository. Of two posts taking the same
+    // slug at once the second runs into the unique index: an explicit slug is taken by then, a
+    // generated one gets suffixed again, this time seeing the first post.
+    let mut attempts = 0;
+    let created_post = loop {
+        attempts += 1;
+        match post_repository::insert(
+            &state.pool,
+            new_post_db.clone(),
+            tags.clone(),
+            !explicit_slug,
+        )
         .await
-        .map_err(PostError::InfraError)?;
+        {
+            Ok(created_post) => break created_post,
+            Err(InfraError::Conflict(_)) if explicit_slug => {
+                return Err(PostError::SlugTaken(new_post_db.slug))
+            }
+            Err(InfraError::Conflict(_)) if attempts < SLUG_ATTEMPTS => continue,
+            Err(db_error) => return Err(PostError::InfraError(db_error)),
+        }
+    };
 
     // Create a PostResponse instance from the newly created post
-    let post_response = PostResponse {
-        id: created_post.id,
-        title: created_post.title,
-        body: created_post.body,
-        published: created_post.published,
-    };
+    let post_response = adapt_post_to_post_response(created_post);
 
     // Return the response as JSON with a success status
     Ok(Json(post_response))
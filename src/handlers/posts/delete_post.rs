@@ -1,29 +1,33 @@
 use crate::domain::models::post::PostError;
-use crate::handlers::posts::PostResponse;
+use crate::handlers::extractors::{Json, Path};
+use crate::handlers::posts::{adapt_post_to_post_response, missing_post_error, PostResponse};
+use crate::infra::errors::InfraError;
 use crate::infra::repositories::post_repository;
+use crate::middlewares::{DeleteAnyPost, PostAuthor};
 use crate::AppState;
-use axum::extract::{Path, State};
-use axum::Json;
+use axum::extract::State;
 use tracing::log::debug;
 use uuid::Uuid;
 
 pub async fn delete_post(
     State(state): State<AppState>,
+    author: PostAuthor<DeleteAnyPost>,
     Path(id): Path<Uuid>,
 ) -> Result<Json<PostResponse>, PostError> {
     debug!("->> {:<12} - delete_post", "HANDLER");
 
-    let deleted_response = post_repository::delete(&state.pool, id)
-        .await
-        .map_err(PostError::InfraError)?;
+    let author_id = author.author_filter();
+    let deleted_response = match post_repository::delete(&state.pool, id, author_id).await {
+        Ok(post) => post,
+        Err(InfraError::NotFound) => {
+            let otherwise = PostError::NotFound(id);
+            return Err(missing_post_error(&state, id, author_id, otherwise).await);
+        }
+        Err(db_error) => return Err(PostError::InfraError(db_error)),
+    };
 
     // Create a PostResponse instance from the deleted post
-    let post_response = PostResponse {
-        id: deleted_response.id,
-        title: deleted_response.title,
-        body: deleted_response.body,
-        published: deleted_response.published,
-    };
+    let post_response = adapt_post_to_post_response(deleted_response);
 
     // Return the response as JSON with a success status
     Ok(Json(post_response))
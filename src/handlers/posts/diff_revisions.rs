@@ -0,0 +1,60 @@
+use crate::domain::models::post::PostError;
+use crate::handlers::extractors::{Json, Path, Query};
+use crate::handlers::posts::{
+    check_post_author, DiffLineResponse, DiffRevisionsParams, RevisionsDiffResponse,
+};
+use crate::infra::errors::InfraError;
+use crate::infra::repositories::post_revision_repository;
+use crate::middlewares::{EditAnyPost, PostAuthor};
+use crate::AppState;
+use axum::extract::State;
+use similar::{ChangeTag, TextDiff};
+use tracing::log::debug;
+use uuid::Uuid;
+
+pub async fn diff_revisions(
+    State(state): State<AppState>,
+    author: PostAuthor<EditAnyPost>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<DiffRevisionsParams>,
+) -> Result<Json<RevisionsDiffResponse>, PostError> {
+    debug!("->> {:<12} - diff_revisions", "HANDLER");
+
+    check_post_author(&state, id, author.author_filter()).await?;
+
+    let mut revisions = Vec::with_capacity(2);
+    for revision in [params.from, params.to] {
+        revisions.push(
+            post_revision_repository::get(&state.pool, id, revision)
+                .await
+                .map_err(|db_error| match db_error {
+                    InfraError::NotFound => PostError::RevisionNotFound(id, revision),
+                    _ => PostError::InfraError(db_error),
+                })?,
+        );
+    }
+    let (from, to) = (&revisions[0], &revisions[1]);
+
+    Ok(Json(RevisionsDiffResponse {
+        from: params.from,
+        to: params.to,
+        title: diff_lines(&from.title, &to.title),
+        body: diff_lines(&from.body, &to.body),
+    }))
+}
+
+// Line-by-line diff where every line is either kept, added or removed
+fn diff_lines(old: &str, new: &str) -> Vec<DiffLineResponse> {
+    TextDiff::from_lines(old, new)
+        .iter_all_changes()
+        .map(|change| DiffLineResponse {
+            op: match change.tag() {
+                ChangeTag::Equal => "equal",
+                ChangeTag::Insert => "insert",
+                ChangeTag::Delete => "delete",
+            }
+            .to_string(),
+            text: change.value().trim_end_matches('\n').to_string(),
+        })
+        .collect()
+}
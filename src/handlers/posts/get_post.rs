@@ -1,12 +1,10 @@
-use crate::domain::models::post::{PostError, PostModel};
-use crate::handlers::posts::PostResponse;
+use crate::domain::models::post::PostError;
+use crate::handlers::extractors::{Json, Path};
+use crate::handlers::posts::{adapt_post_to_post_response, PostResponse};
 use crate::infra::errors::InfraError;
 use crate::infra::repositories::post_repository;
 use crate::AppState;
-use axum::{
-    extract::{Path, State},
-    Json,
-};
+use axum::extract::State;
 use tracing::log::debug;
 use uuid::Uuid;
 
@@ -19,18 +17,9 @@ pub async fn get_post(
     let post = post_repository::get(&state.pool, id)
         .await
         .map_err(|db_error| match db_error {
-            InfraError::InternalServerError => PostError::InternalServerError,
             InfraError::NotFound => PostError::NotFound(id),
+            _ => PostError::InfraError(db_error),
         })?;
 
     Ok(Json(adapt_post_to_post_response(post)))
 }
-
-fn adapt_post_to_post_response(post: PostModel) -> PostResponse {
-    PostResponse {
-        id: post.id,
-        title: post.title,
-        body: post.body,
-        published: post.published,
-    }
-}
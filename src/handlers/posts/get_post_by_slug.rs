@@ -0,0 +1,42 @@
+use crate::domain::models::post::PostError;
+use crate::handlers::extractors::{Json, Path};
+use crate::handlers::posts::adapt_post_to_post_response;
+use crate::infra::errors::InfraError;
+use crate::infra::repositories::post_repository;
+use crate::AppState;
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use tracing::log::debug;
+
+pub async fn get_post_by_slug(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> Result<Response, PostError> {
+    debug!("->> {:<12} - get_post_by_slug", "HANDLER");
+
+    match post_repository::get_by_slug(&state.pool, slug.clone()).await {
+        Ok(post) => return Ok(Json(adapt_post_to_post_response(post)).into_response()),
+        Err(InfraError::NotFound) => {}
+        Err(db_error) => return Err(PostError::InfraError(db_error)),
+    }
+
+    // The slug may have been renamed, in which case the old link moves permanently
+    let current_slug = post_repository::get_current_slug(&state.pool, slug.clone())
+        .await
+        .map_err(|db_error| match db_error {
+            InfraError::NotFound => PostError::SlugNotFound(slug),
+            _ => PostError::InfraError(db_error),
+        })?;
+
+    Ok((
+        StatusCode::MOVED_PERMANENTLY,
+        [(
+            header::LOCATION,
+            format!("/api/post/by-slug/{}", current_slug),
+        )],
+    )
+        .into_response())
+}
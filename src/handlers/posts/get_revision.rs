@@ -0,0 +1,31 @@
+use crate::domain::models::post::PostError;
+use crate::handlers::extractors::{Json, Path};
+use crate::handlers::posts::{
+    adapt_revision_to_revision_response, check_post_author, PostRevisionResponse,
+};
+use crate::infra::errors::InfraError;
+use crate::infra::repositories::post_revision_repository;
+use crate::middlewares::{EditAnyPost, PostAuthor};
+use crate::AppState;
+use axum::extract::State;
+use tracing::log::debug;
+use uuid::Uuid;
+
+pub async fn get_revision(
+    State(state): State<AppState>,
+    author: PostAuthor<EditAnyPost>,
+    Path((id, revision)): Path<(Uuid, i32)>,
+) -> Result<Json<PostRevisionResponse>, PostError> {
+    debug!("->> {:<12} - get_revision", "HANDLER");
+
+    check_post_author(&state, id, author.author_filter()).await?;
+
+    let revision = post_revision_repository::get(&state.pool, id, revision)
+        .await
+        .map_err(|db_error| match db_error {
+            InfraError::NotFound => PostError::RevisionNotFound(id, revision),
+            _ => PostError::InfraError(db_error),
+        })?;
+
+    Ok(Json(adapt_revision_to_revision_response(revision)))
+}
@@ -1,11 +1,11 @@
 // Import necessary modules and types
-use axum::extract::{Query, State};
-use axum::Json;
+use crate::handlers::extractors::{Json, Query};
+use axum::extract::State;
 use tracing::log::debug;
 
 // Import internal modules and types
-use crate::domain::models::post::{PostError, PostModel};
-use crate::handlers::posts::{ListPostsResponse, PostResponse};
+use crate::domain::models::post::{PostError, PostsPage};
+use crate::handlers::posts::{adapt_post_to_post_response, ListPostsResponse, PostResponse};
 use crate::infra::repositories::post_repository::{get_all, PostsFilter};
 use crate::AppState;
 
@@ -16,32 +16,39 @@ pub async fn list_posts(
 ) -> Result<Json<ListPostsResponse>, PostError> {
     debug!("->> {:<12} - list_posts", "HANDLER");
 
-    let posts = get_all(&state.pool, params)
+    if !params.cursor_matches_sort() {
+        return Err(PostError::InvalidCursor);
+    }
+
+    let page = get_all(&state.pool, params)
         .await
-        .map_err(|_| PostError::InternalServerError)?;
+        .map_err(PostError::InfraError)?;
 
-    // Convert the retrieved list of PostModel instances to a ListPostsResponse
-    Ok(Json(adapt_posts_to_list_posts_response(posts)))
+    // Convert the retrieved page of PostModel instances to a ListPostsResponse
+    Ok(Json(adapt_posts_to_list_posts_response(page)))
 }
 
-// Helper function to adapt a single PostModel to a PostResponse
-fn adapt_post_to_post_response(post: PostModel) -> PostResponse {
-    PostResponse {
-        id: post.id,
-        title: post.title,
-        body: post.body,
-        published: post.published,
-    }
-}
+// Helper function to adapt a page of PostModel instances to a ListPostsResponse
+fn adapt_posts_to_list_posts_response(page: PostsPage) -> ListPostsResponse {
+    let mut snippets = page.snippets;
 
-// Helper function to adapt a list of PostModel instances to a ListPostsResponse
-fn adapt_posts_to_list_posts_response(posts: Vec<PostModel>) -> ListPostsResponse {
-    // Map each PostModel to a PostResponse and collect them into a Vec<PostResponse>
-    let posts_response: Vec<PostResponse> =
-        posts.into_iter().map(adapt_post_to_post_response).collect();
+    // Map each PostModel to a PostResponse, attaching its search snippet if there is one
+    let posts_response: Vec<PostResponse> = page
+        .posts
+        .into_iter()
+        .map(|post| {
+            let snippet = snippets.remove(&post.id);
+            PostResponse {
+                snippet,
+                ..adapt_post_to_post_response(post)
+            }
+        })
+        .collect();
 
-    // Create a ListPostsResponse containing the list of PostResponses
+    // Create a ListPostsResponse containing the list of PostResponses and paging details
     ListPostsResponse {
         posts: posts_response,
+        next_cursor: page.next_cursor,
+        total: page.total,
     }
 }
@@ -0,0 +1,32 @@
+use crate::domain::models::post::PostError;
+use crate::handlers::extractors::{Json, Path};
+use crate::handlers::posts::{
+    adapt_revision_to_revision_response, check_post_author, ListPostRevisionsResponse,
+};
+use crate::infra::repositories::post_revision_repository;
+use crate::middlewares::{EditAnyPost, PostAuthor};
+use crate::AppState;
+use axum::extract::State;
+use tracing::log::debug;
+use uuid::Uuid;
+
+pub async fn list_revisions(
+    State(state): State<AppState>,
+    author: PostAuthor<EditAnyPost>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ListPostRevisionsResponse>, PostError> {
+    debug!("->> {:<12} - list_revisions", "HANDLER");
+
+    check_post_author(&state, id, author.author_filter()).await?;
+
+    let revisions = post_revision_repository::get_all(&state.pool, id)
+        .await
+        .map_err(PostError::InfraError)?;
+
+    Ok(Json(ListPostRevisionsResponse {
+        revisions: revisions
+            .into_iter()
+            .map(adapt_revision_to_revision_response)
+            .collect(),
+    }))
+}
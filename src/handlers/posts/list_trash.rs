@@ -0,0 +1,24 @@
+use crate::domain::models::post::PostError;
+use crate::handlers::extractors::Json;
+use crate::handlers::posts::{adapt_post_to_post_response, TrashResponse};
+use crate::infra::repositories::post_repository;
+use crate::middlewares::{DeleteAnyPost, PostAuthor};
+use crate::AppState;
+use axum::extract::State;
+use tracing::log::debug;
+
+pub async fn list_trash(
+    State(state): State<AppState>,
+    author: PostAuthor<DeleteAnyPost>,
+) -> Result<Json<TrashResponse>, PostError> {
+    debug!("->> {:<12} - list_trash", "HANDLER");
+
+    // Authors see their own trash, whoever may delete any post sees all of it
+    let posts = post_repository::get_trash(&state.pool, author.author_filter())
+        .await
+        .map_err(PostError::InfraError)?;
+
+    Ok(Json(TrashResponse {
+        posts: posts.into_iter().map(adapt_post_to_post_response).collect(),
+    }))
+}
@@ -1,10 +1,29 @@
+use crate::config::Config;
+use crate::domain::models::post::{PostError, PostModel};
+use crate::domain::models::post_revision::PostRevisionModel;
+use crate::handlers::validation::{Validate, Validator};
+use crate::infra::errors::InfraError;
+use crate::infra::repositories::post_repository;
+use crate::AppState;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+const MAX_SLUG_LENGTH: usize = 100;
+
+pub mod cancel_schedule;
 pub mod create_post;
 pub mod delete_post;
+pub mod diff_revisions;
 pub mod get_post;
+pub mod get_post_by_slug;
+pub mod get_revision;
 pub mod list_posts;
+pub mod list_revisions;
+pub mod list_trash;
+pub mod restore_post;
+pub mod restore_revision;
+pub mod schedule_post;
 pub mod update_post;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -13,12 +32,25 @@ pub struct PostResponse {
     title: String,
     body: String,
     published: bool,
+    author_id: Option<Uuid>,
+    author_email: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    published_at: Option<DateTime<Utc>>,
+    deleted_at: Option<DateTime<Utc>>,
+    publish_at: Option<DateTime<Utc>>,
+    slug: String,
+    tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    snippet: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct CreatePostRequest {
     title: String,
     body: String,
+    slug: Option<String>,
+    tags: Option<Vec<String>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -26,9 +58,220 @@ pub struct UpdatePostRequest {
     pub title: Option<String>,
     pub body: Option<String>,
     pub published: Option<bool>,
+    pub slug: Option<String>,
+    pub tags: Option<Vec<String>>,
+}
+
+impl Validate for CreatePostRequest {
+    fn validate(&self, config: &Config, validator: &mut Validator) {
+        validate_title(config, validator, &self.title);
+        validate_body(config, validator, &self.body);
+    }
+}
+
+impl Validate for UpdatePostRequest {
+    fn validate(&self, config: &Config, validator: &mut Validator) {
+        if let Some(title) = &self.title {
+            validate_title(config, validator, title);
+        }
+        if let Some(body) = &self.body {
+            validate_body(config, validator, body);
+        }
+    }
+}
+
+fn validate_title(config: &Config, validator: &mut Validator, title: &str) {
+    validator
+        .field("title", title)
+        .not_blank()
+        .no_control_chars()
+        .max_chars(config.post_title_max_chars());
+}
+
+// Newlines and tabs are fine in a body, NUL bytes can't even be stored
+fn validate_body(config: &Config, validator: &mut Validator, body: &str) {
+    validator
+        .field("body", body)
+        .not_blank()
+        .no_nul()
+        .max_bytes(config.post_body_max_bytes());
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SchedulePostRequest {
+    publish_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ListPostsResponse {
     posts: Vec<PostResponse>,
+    next_cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrashResponse {
+    posts: Vec<PostResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostRevisionResponse {
+    id: Uuid,
+    post_id: Uuid,
+    revision: i32,
+    title: String,
+    body: String,
+    editor_id: Option<Uuid>,
+    restored_from: Option<i32>,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListPostRevisionsResponse {
+    revisions: Vec<PostRevisionResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DiffRevisionsParams {
+    from: i32,
+    to: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiffLineResponse {
+    op: String,
+    text: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RevisionsDiffResponse {
+    from: i32,
+    to: i32,
+    title: Vec<DiffLineResponse>,
+    body: Vec<DiffLineResponse>,
+}
+
+// Helper function to adapt a single PostModel to a PostResponse
+pub fn adapt_post_to_post_response(post: PostModel) -> PostResponse {
+    PostResponse {
+        id: post.id,
+        title: post.title,
+        body: post.body,
+        published: post.published,
+        author_id: post.author_id,
+        author_email: post.author_email,
+        created_at: post.created_at,
+        updated_at: post.updated_at,
+        published_at: post.published_at,
+        deleted_at: post.deleted_at,
+        publish_at: post.publish_at,
+        slug: post.slug,
+        tags: post.tags,
+        snippet: None,
+    }
+}
+
+// Helper function to adapt a single PostRevisionModel to a PostRevisionResponse
+pub fn adapt_revision_to_revision_response(revision: PostRevisionModel) -> PostRevisionResponse {
+    PostRevisionResponse {
+        id: revision.id,
+        post_id: revision.post_id,
+        revision: revision.revision,
+        title: revision.title,
+        body: revision.body,
+        editor_id: revision.editor_id,
+        restored_from: revision.restored_from,
+        created_at: revision.created_at,
+    }
+}
+
+// Make sure the post is by `author` before its history gets exposed; with no author, as for users
+// who may act on the posts of others, any post will do
+pub async fn check_post_author(
+    state: &AppState,
+    id: Uuid,
+    author: Option<Uuid>,
+) -> Result<(), PostError> {
+    check_authorship(post_repository::get(&state.pool, id).await, id, author)
+}
+
+// Writes on behalf of an author only change their own posts, so when nothing was changed, look up
+// whether the post is missing or someone else's. `otherwise` is the error if it's neither.
+pub async fn missing_post_error(
+    state: &AppState,
+    id: Uuid,
+    author: Option<Uuid>,
+    otherwise: PostError,
+) -> PostError {
+    let post = post_repository::get(&state.pool, id).await;
+    check_authorship(post, id, author)
+        .err()
+        .unwrap_or(otherwise)
+}
+
+pub fn check_authorship(
+    post: Result<PostModel, InfraError>,
+    id: Uuid,
+    author: Option<Uuid>,
+) -> Result<(), PostError> {
+    let post = post.map_err(|db_error| match db_error {
+        InfraError::NotFound => PostError::NotFound(id),
+        _ => PostError::InfraError(db_error),
+    })?;
+
+    if author.is_some_and(|author| post.author_id != Some(author)) {
+        return Err(PostError::Forbidden(id));
+    }
+
+    Ok(())
+}
+
+// Transliterate text into lowercase ASCII words joined by dashes, e.g. "Crème brûlée!" becomes
+// "creme-brulee"
+pub fn slugify(text: &str) -> String {
+    let slug = slug::slugify(text);
+
+    // Slugs are pure ASCII, so cutting them at any byte is safe
+    slug[..slug.len().min(MAX_SLUG_LENGTH)]
+        .trim_end_matches('-')
+        .to_string()
+}
+
+// Normalize a slug picked by the user and make sure no other post holds it
+pub async fn claim_slug(
+    state: &AppState,
+    requested: &str,
+    post_id: Option<Uuid>,
+) -> Result<String, PostError> {
+    let slug = slugify(requested);
+    if slug.is_empty() {
+        return Err(PostError::InvalidSlug);
+    }
+
+    let available = post_repository::is_slug_available(&state.pool, slug.clone(), post_id)
+        .await
+        .map_err(PostError::InfraError)?;
+    if !available {
+        return Err(PostError::SlugTaken(slug));
+    }
+
+    Ok(slug)
+}
+
+// Tags are slugs too, so "Web Dev" and "web-dev" end up as the same tag
+pub fn normalize_tags(tags: Vec<String>) -> Result<Vec<String>, PostError> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+
+    for tag in tags {
+        let name = slugify(&tag);
+        if name.is_empty() {
+            return Err(PostError::InvalidTag(tag));
+        }
+        if !normalized.contains(&name) {
+            normalized.push(name);
+        }
+    }
+
+    Ok(normalized)
 }
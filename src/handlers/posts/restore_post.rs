@@ -0,0 +1,32 @@
+use crate::domain::models::post::PostError;
+use crate::handlers::extractors::{Json, Path};
+use crate::handlers::posts::{adapt_post_to_post_response, check_authorship, PostResponse};
+use crate::infra::errors::InfraError;
+use crate::infra::repositories::post_repository;
+use crate::middlewares::{DeleteAnyPost, PostAuthor};
+use crate::AppState;
+use axum::extract::State;
+use tracing::log::debug;
+use uuid::Uuid;
+
+pub async fn restore_post(
+    State(state): State<AppState>,
+    author: PostAuthor<DeleteAnyPost>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<PostResponse>, PostError> {
+    debug!("->> {:<12} - restore_post", "HANDLER");
+
+    // Only the author, or whoever may delete any post, may take it out of the trash
+    let author_id = author.author_filter();
+    let restored_post = match post_repository::restore(&state.pool, id, author_id).await {
+        Ok(post) => post,
+        Err(InfraError::NotFound) => {
+            let trashed_post = post_repository::get_trashed(&state.pool, id).await;
+            check_authorship(trashed_post, id, author_id)?;
+            return Err(PostError::NotFound(id));
+        }
+        Err(db_error) => return Err(PostError::InfraError(db_error)),
+    };
+
+    Ok(Json(adapt_post_to_post_response(restored_post)))
+}
@@ -0,0 +1,35 @@
+use crate::domain::models::post::PostError;
+use crate::handlers::extractors::{Json, Path};
+use crate::handlers::posts::{adapt_post_to_post_response, missing_post_error, PostResponse};
+use crate::infra::errors::InfraError;
+use crate::infra::repositories::post_repository;
+use crate::middlewares::{EditAnyPost, PostAuthor};
+use crate::AppState;
+use axum::extract::State;
+use tracing::log::debug;
+use uuid::Uuid;
+
+pub async fn restore_revision(
+    State(state): State<AppState>,
+    author: PostAuthor<EditAnyPost>,
+    Path((id, revision)): Path<(Uuid, i32)>,
+) -> Result<Json<PostResponse>, PostError> {
+    debug!("->> {:<12} - restore_revision", "HANDLER");
+
+    let author_id = author.author_filter();
+    let restored =
+        post_repository::restore_revision(&state.pool, id, revision, author.0.user_id, author_id)
+            .await;
+    let restored_post = match restored {
+        Ok(post) => post,
+        // A post that is there and may be changed can only be missing the revision
+        Err(InfraError::NotFound) => {
+            let otherwise = PostError::RevisionNotFound(id, revision);
+            return Err(missing_post_error(&state, id, author_id, otherwise).await);
+        }
+        Err(db_error) => return Err(PostError::InfraError(db_error)),
+    };
+
+    // Return the post as it is now, with the restored content
+    Ok(Json(adapt_post_to_post_response(restored_post)))
+}
@@ -0,0 +1,43 @@
+use crate::domain::models::post::PostError;
+use crate::handlers::extractors::{Json, Path};
+use crate::handlers::posts::{
+    adapt_post_to_post_response, missing_post_error, PostResponse, SchedulePostRequest,
+};
+use crate::infra::errors::InfraError;
+use crate::infra::repositories::post_repository;
+use crate::middlewares::{Authorized, EditAnyPost, PostAuthor, PublishPosts};
+use crate::AppState;
+use axum::extract::State;
+use chrono::Utc;
+use tracing::log::debug;
+use uuid::Uuid;
+
+pub async fn schedule_post(
+    State(state): State<AppState>,
+    _: Authorized<PublishPosts>,
+    author: PostAuthor<EditAnyPost>,
+    Path(id): Path<Uuid>,
+    Json(schedule): Json<SchedulePostRequest>,
+) -> Result<Json<PostResponse>, PostError> {
+    debug!("->> {:<12} - schedule_post", "HANDLER");
+
+    if schedule.publish_at <= Utc::now() {
+        return Err(PostError::PublishAtInPast);
+    }
+
+    // Scheduling again simply moves the publication time
+    let author_id = author.author_filter();
+    let publish_at = Some(schedule.publish_at);
+    let scheduled_post =
+        match post_repository::schedule(&state.pool, id, publish_at, author_id).await {
+            Ok(post) => post,
+            // A post that is there and may be changed can only be live already
+            Err(InfraError::NotFound) => {
+                let otherwise = PostError::AlreadyPublished(id);
+                return Err(missing_post_error(&state, id, author_id, otherwise).await);
+            }
+            Err(db_error) => return Err(PostError::InfraError(db_error)),
+        };
+
+    Ok(Json(adapt_post_to_post_response(scheduled_post)))
+}
@@ -1,31 +1,62 @@
+use crate::domain::models::auth::AuthError;
 use crate::domain::models::post::PostError;
-use crate::handlers::posts::{PostResponse, UpdatePostRequest};
+use crate::domain::models::role::Permission;
+use crate::handlers::extractors::{Json, Path, ValidJson};
+use crate::handlers::posts::{
+    adapt_post_to_post_response, claim_slug, missing_post_error, normalize_tags, PostResponse,
+    UpdatePostRequest,
+};
+use crate::infra::errors::InfraError;
 use crate::infra::repositories::post_repository;
+use crate::middlewares::{EditAnyPost, PostAuthor};
 use crate::AppState;
-use axum::extract::{Path, State};
-use axum::Json;
+use axum::extract::State;
 use tracing::log::debug;
 use uuid::Uuid;
 
 pub async fn update_post(
     State(state): State<AppState>,
+    author: PostAuthor<EditAnyPost>,
     Path(id): Path<Uuid>,
-    Json(updated_post): Json<UpdatePostRequest>,
+    ValidJson(mut updated_post): ValidJson<UpdatePostRequest>,
 ) -> Result<Json<PostResponse>, PostError> {
     debug!("->> {:<12} - update_post", "HANDLER");
 
-    let updated_response = post_repository::update(&state.pool, id, updated_post)
-        .await
-        .map_err(PostError::InfraError)?;
+    // Whether publishing is allowed too depends on what the update does
+    if updated_post.published == Some(true) && !author.0.has_permission(Permission::PostPublish) {
+        return Err(AuthError::MissingPermission(Permission::PostPublish).into());
+    }
 
-    // Create a PostResponse instance from the newly updated post
-    let post_response = PostResponse {
-        id: updated_response.id,
-        title: updated_response.title,
-        body: updated_response.body,
-        published: updated_response.published,
+    if let Some(requested) = &updated_post.slug {
+        updated_post.slug = Some(claim_slug(&state, requested, Some(id)).await?);
+    }
+
+    if let Some(title) = &updated_post.title {
+        updated_post.title = Some(title.trim().to_string());
+    }
+
+    if let Some(tags) = updated_post.tags.take() {
+        updated_post.tags = Some(normalize_tags(tags)?);
+    }
+
+    // Another post may have taken the slug since it was checked
+    let slug = updated_post.slug.clone();
+    let author_id = author.author_filter();
+    let updated =
+        post_repository::update(&state.pool, id, updated_post, author.0.user_id, author_id).await;
+    let updated_response = match (updated, slug) {
+        (Ok(post), _) => post,
+        (Err(InfraError::NotFound), _) => {
+            let otherwise = PostError::NotFound(id);
+            return Err(missing_post_error(&state, id, author_id, otherwise).await);
+        }
+        (Err(InfraError::Conflict(_)), Some(slug)) => return Err(PostError::SlugTaken(slug)),
+        (Err(db_error), _) => return Err(PostError::InfraError(db_error)),
     };
 
+    // Create a PostResponse instance from the newly updated post
+    let post_response = adapt_post_to_post_response(updated_response);
+
     // Return the response as JSON with a success status
     Ok(Json(post_response))
 }
@@ -0,0 +1,26 @@
+use crate::domain::models::tag::{TagError, TagModel};
+use crate::handlers::extractors::Json;
+use crate::handlers::tags::{ListTagsResponse, TagResponse};
+use crate::infra::repositories::tag_repository;
+use crate::AppState;
+use axum::extract::State;
+use tracing::log::debug;
+
+pub async fn list_tags(State(state): State<AppState>) -> Result<Json<ListTagsResponse>, TagError> {
+    debug!("->> {:<12} - list_tags", "HANDLER");
+
+    let tags = tag_repository::get_all_with_counts(&state.pool)
+        .await
+        .map_err(TagError::InfraError)?;
+
+    Ok(Json(ListTagsResponse {
+        tags: tags.into_iter().map(adapt_tag_to_tag_response).collect(),
+    }))
+}
+
+fn adapt_tag_to_tag_response(tag: TagModel) -> TagResponse {
+    TagResponse {
+        name: tag.name,
+        post_count: tag.post_count,
+    }
+}
@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+pub mod list_tags;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TagResponse {
+    name: String,
+    post_count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListTagsResponse {
+    tags: Vec<TagResponse>,
+}
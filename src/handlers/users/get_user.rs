@@ -0,0 +1,28 @@
+use crate::domain::models::user::UserError;
+use crate::handlers::extractors::{Json, Path};
+use crate::handlers::users::{adapt_overview_to_overview_response, UserOverviewResponse};
+use crate::infra::errors::InfraError;
+use crate::infra::repositories::user_repository;
+use crate::middlewares::{Authorized, ManageUsers};
+use crate::AppState;
+use axum::extract::State;
+use chrono::Utc;
+use tracing::log::debug;
+use uuid::Uuid;
+
+pub async fn get_user(
+    State(state): State<AppState>,
+    Authorized(_, _): Authorized<ManageUsers>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<UserOverviewResponse>, UserError> {
+    debug!("->> {:<12} - get_user", "HANDLER");
+
+    let overview = user_repository::get_overview(&state.pool, id, Utc::now().timestamp())
+        .await
+        .map_err(|db_error| match db_error {
+            InfraError::NotFound => UserError::NotFound(id),
+            _ => UserError::InfraError(db_error),
+        })?;
+
+    Ok(Json(adapt_overview_to_overview_response(overview)))
+}
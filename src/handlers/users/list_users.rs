@@ -0,0 +1,29 @@
+use crate::domain::models::user::UserError;
+use crate::handlers::extractors::{Json, Query};
+use crate::handlers::users::{adapt_user_to_user_response, ListUsersResponse};
+use crate::infra::repositories::user_repository::{self, UsersFilter};
+use crate::middlewares::{Authorized, ManageUsers};
+use crate::AppState;
+use axum::extract::State;
+use tracing::log::debug;
+
+pub async fn list_users(
+    State(state): State<AppState>,
+    Authorized(_, _): Authorized<ManageUsers>,
+    Query(params): Query<UsersFilter>,
+) -> Result<Json<ListUsersResponse>, UserError> {
+    debug!("->> {:<12} - list_users", "HANDLER");
+
+    let page = user_repository::get_all(&state.pool, params)
+        .await
+        .map_err(UserError::InfraError)?;
+
+    Ok(Json(ListUsersResponse {
+        users: page
+            .users
+            .into_iter()
+            .map(adapt_user_to_user_response)
+            .collect(),
+        next_cursor: page.next_cursor,
+    }))
+}
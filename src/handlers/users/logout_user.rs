@@ -0,0 +1,41 @@
+use crate::domain::models::user::UserError;
+use crate::handlers::extractors::{Json, Path};
+use crate::handlers::users::LogoutUserResponse;
+use crate::infra::errors::InfraError;
+use crate::infra::repositories::{
+    personal_access_tokens_repository, user_repository, user_sessions_repository,
+};
+use crate::middlewares::{Authorized, ManageUsers};
+use crate::AppState;
+use axum::extract::State;
+use tracing::log::debug;
+use uuid::Uuid;
+
+// Cut a user off: end every session, wherever they are logged in, and revoke every access token
+pub async fn logout_user(
+    State(state): State<AppState>,
+    Authorized(_, _): Authorized<ManageUsers>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<LogoutUserResponse>, UserError> {
+    debug!("->> {:<12} - logout_user", "HANDLER");
+
+    user_repository::get(&state.pool, id)
+        .await
+        .map_err(|db_error| match db_error {
+            InfraError::NotFound => UserError::NotFound(id),
+            _ => UserError::InfraError(db_error),
+        })?;
+
+    let revoked_sessions = user_sessions_repository::delete_all_by_user(&state.pool, id)
+        .await
+        .map_err(UserError::InfraError)?;
+
+    let revoked_tokens = personal_access_tokens_repository::delete_all_by_user(&state.pool, id)
+        .await
+        .map_err(UserError::InfraError)?;
+
+    Ok(Json(LogoutUserResponse {
+        revoked_sessions,
+        revoked_tokens,
+    }))
+}
@@ -0,0 +1,77 @@
+use crate::domain::models::role::Role;
+use crate::domain::models::user::{UserError, UserModel, UserOverviewModel};
+use crate::handlers::auth::UserData;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub mod get_user;
+pub mod list_users;
+pub mod logout_user;
+pub mod suspend_user;
+pub mod unsuspend_user;
+pub mod update_user_role;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserResponse {
+    id: Uuid,
+    email: String,
+    role: Role,
+    created_at: DateTime<Utc>,
+    suspended_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserOverviewResponse {
+    #[serde(flatten)]
+    user: UserResponse,
+    session_count: i64,
+    post_count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListUsersResponse {
+    users: Vec<UserResponse>,
+    next_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateUserRoleRequest {
+    role: Role,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LogoutUserResponse {
+    revoked_sessions: usize,
+    revoked_tokens: usize,
+}
+
+// Helper function to adapt a UserModel to a UserResponse
+pub fn adapt_user_to_user_response(user: UserModel) -> UserResponse {
+    UserResponse {
+        id: user.id,
+        email: user.email,
+        role: user.role,
+        created_at: user.created_at,
+        suspended_at: user.suspended_at,
+    }
+}
+
+// Helper function to adapt a UserOverviewModel to a UserOverviewResponse
+pub fn adapt_overview_to_overview_response(overview: UserOverviewModel) -> UserOverviewResponse {
+    UserOverviewResponse {
+        user: adapt_user_to_user_response(overview.user),
+        session_count: overview.session_count,
+        post_count: overview.post_count,
+    }
+}
+
+// Admins can't lock themselves out: whatever they do to their own account, someone has to be left
+// to undo it
+pub fn check_not_own_account(user_data: &UserData, id: Uuid) -> Result<(), UserError> {
+    if user_data.user_id == id {
+        return Err(UserError::OwnAccount);
+    }
+
+    Ok(())
+}
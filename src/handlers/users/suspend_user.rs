@@ -0,0 +1,31 @@
+use crate::domain::models::user::UserError;
+use crate::handlers::extractors::{Json, Path};
+use crate::handlers::users::{adapt_user_to_user_response, check_not_own_account, UserResponse};
+use crate::infra::errors::InfraError;
+use crate::infra::repositories::user_repository;
+use crate::middlewares::{Authorized, ManageUsers};
+use crate::AppState;
+use axum::extract::State;
+use chrono::Utc;
+use tracing::log::debug;
+use uuid::Uuid;
+
+// Suspended users are treated as logged out on every request until they are unsuspended
+pub async fn suspend_user(
+    State(state): State<AppState>,
+    Authorized(user_data, _): Authorized<ManageUsers>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<UserResponse>, UserError> {
+    debug!("->> {:<12} - suspend_user", "HANDLER");
+
+    check_not_own_account(&user_data, id)?;
+
+    let user = user_repository::set_suspended(&state.pool, id, Some(Utc::now()))
+        .await
+        .map_err(|db_error| match db_error {
+            InfraError::NotFound => UserError::NotFound(id),
+            _ => UserError::InfraError(db_error),
+        })?;
+
+    Ok(Json(adapt_user_to_user_response(user)))
+}
@@ -0,0 +1,27 @@
+use crate::domain::models::user::UserError;
+use crate::handlers::extractors::{Json, Path};
+use crate::handlers::users::{adapt_user_to_user_response, UserResponse};
+use crate::infra::errors::InfraError;
+use crate::infra::repositories::user_repository;
+use crate::middlewares::{Authorized, ManageUsers};
+use crate::AppState;
+use axum::extract::State;
+use tracing::log::debug;
+use uuid::Uuid;
+
+pub async fn unsuspend_user(
+    State(state): State<AppState>,
+    Authorized(_, _): Authorized<ManageUsers>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<UserResponse>, UserError> {
+    debug!("->> {:<12} - unsuspend_user", "HANDLER");
+
+    let user = user_repository::set_suspended(&state.pool, id, None)
+        .await
+        .map_err(|db_error| match db_error {
+            InfraError::NotFound => UserError::NotFound(id),
+            _ => UserError::InfraError(db_error),
+        })?;
+
+    Ok(Json(adapt_user_to_user_response(user)))
+}
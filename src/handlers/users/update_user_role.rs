@@ -0,0 +1,33 @@
+use crate::domain::models::user::UserError;
+use crate::handlers::extractors::{Json, Path};
+use crate::handlers::users::{
+    adapt_user_to_user_response, check_not_own_account, UpdateUserRoleRequest, UserResponse,
+};
+use crate::infra::errors::InfraError;
+use crate::infra::repositories::user_repository;
+use crate::middlewares::{Authorized, ManageUsers};
+use crate::AppState;
+use axum::extract::State;
+use tracing::log::debug;
+use uuid::Uuid;
+
+// The new role applies from the next request of the user on, sessions are kept
+pub async fn update_user_role(
+    State(state): State<AppState>,
+    Authorized(user_data, _): Authorized<ManageUsers>,
+    Path(id): Path<Uuid>,
+    Json(update): Json<UpdateUserRoleRequest>,
+) -> Result<Json<UserResponse>, UserError> {
+    debug!("->> {:<12} - update_user_role", "HANDLER");
+
+    check_not_own_account(&user_data, id)?;
+
+    let user = user_repository::set_role(&state.pool, id, update.role)
+        .await
+        .map_err(|db_error| match db_error {
+            InfraError::NotFound => UserError::NotFound(id),
+            _ => UserError::InfraError(db_error),
+        })?;
+
+    Ok(Json(adapt_user_to_user_response(user)))
+}
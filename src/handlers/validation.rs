@@ -0,0 +1,176 @@
+use crate::config::Config;
+use crate::domain::problem::FieldError;
+
+// A request payload that has rules for its fields, checked by `ValidJson` before the handler runs
+pub trait Validate {
+    fn validate(&self, config: &Config, validator: &mut Validator);
+}
+
+// Collects what is wrong with a payload, so all of it can be reported at once
+#[derive(Default)]
+pub struct Validator {
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    pub fn field<'a>(&'a mut self, name: &'static str, value: &'a str) -> FieldRules<'a> {
+        FieldRules {
+            validator: self,
+            name,
+            value,
+            failed: false,
+        }
+    }
+
+    pub fn into_errors(self) -> Vec<FieldError> {
+        self.errors
+    }
+}
+
+// The rules for one field, e.g. `validator.field("title", &title).not_blank().max_chars(200)`.
+// Only the first rule a field breaks is reported.
+pub struct FieldRules<'a> {
+    validator: &'a mut Validator,
+    name: &'static str,
+    value: &'a str,
+    failed: bool,
+}
+
+impl FieldRules<'_> {
+    pub fn not_blank(self) -> Self {
+        let blank = self.value.trim().is_empty();
+        self.check(!blank, || String::from("must not be blank"))
+    }
+
+    pub fn max_chars(self, max: usize) -> Self {
+        let chars = self.value.chars().count();
+        self.check(chars <= max, || {
+            format!("must be at most {} characters", max)
+        })
+    }
+
+    pub fn max_bytes(self, max: usize) -> Self {
+        let bytes = self.value.len();
+        self.check(bytes <= max, || format!("must be at most {} bytes", max))
+    }
+
+    pub fn no_nul(self) -> Self {
+        let nul = self.value.contains('\0');
+        self.check(!nul, || String::from("must not contain NUL bytes"))
+    }
+
+    pub fn no_control_chars(self) -> Self {
+        let control = self.value.chars().any(char::is_control);
+        self.check(!control, || {
+            String::from("must not contain control characters")
+        })
+    }
+
+    fn check(mut self, ok: bool, message: impl FnOnce() -> String) -> Self {
+        if !ok && !self.failed {
+            self.failed = true;
+            self.validator.errors.push(FieldError {
+                field: self.name.to_string(),
+                message: message(),
+            });
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(validator: Validator) -> Vec<(String, String)> {
+        validator
+            .into_errors()
+            .into_iter()
+            .map(|error| (error.field, error.message))
+            .collect()
+    }
+
+    #[test]
+    fn collects_errors_of_every_field() {
+        let mut validator = Validator::default();
+        validator.field("title", " ").not_blank();
+        validator.field("body", "ok").not_blank();
+        validator.field("slug", "a\0b").no_nul();
+
+        assert_eq!(
+            messages(validator),
+            vec![
+                (String::from("title"), String::from("must not be blank")),
+                (
+                    String::from("slug"),
+                    String::from("must not contain NUL bytes")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_only_the_first_broken_rule_of_a_field() {
+        let mut validator = Validator::default();
+        validator
+            .field("title", "\t")
+            .not_blank()
+            .no_control_chars()
+            .max_chars(0);
+
+        assert_eq!(
+            messages(validator),
+            vec![(String::from("title"), String::from("must not be blank"))]
+        );
+    }
+
+    #[test]
+    fn counts_characters_and_bytes_apart() {
+        // Four characters, eight bytes
+        let value = "äöüß";
+
+        let mut validator = Validator::default();
+        validator.field("chars", value).max_chars(4);
+        validator.field("bytes", value).max_bytes(8);
+        assert!(messages(validator).is_empty());
+
+        let mut validator = Validator::default();
+        validator.field("chars", value).max_chars(3);
+        validator.field("bytes", value).max_bytes(7);
+        assert_eq!(
+            messages(validator),
+            vec![
+                (
+                    String::from("chars"),
+                    String::from("must be at most 3 characters")
+                ),
+                (
+                    String::from("bytes"),
+                    String::from("must be at most 7 bytes")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn tells_nul_bytes_from_other_control_characters() {
+        let mut validator = Validator::default();
+        validator.field("body", "line\nnext\tcell").no_nul();
+        validator.field("title", "line\nnext").no_control_chars();
+        validator.field("other", "a\0b").no_control_chars();
+
+        assert_eq!(
+            messages(validator),
+            vec![
+                (
+                    String::from("title"),
+                    String::from("must not contain control characters")
+                ),
+                (
+                    String::from("other"),
+                    String::from("must not contain control characters")
+                ),
+            ]
+        );
+    }
+}
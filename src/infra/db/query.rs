@@ -0,0 +1,49 @@
+use diesel::{PgConnection, QueryResult};
+
+// How many rows `delete_in_batches` removes at a time
+const DELETE_BATCH_SIZE: i64 = 500;
+
+// Delete rows in small batches so a large backlog doesn't hold locks on the table for long.
+// `delete_batch` removes up to the given number of rows and says how many it did; it is called
+// until a batch comes up short. Returns how many rows were removed in all.
+pub fn delete_in_batches<F>(conn: &mut PgConnection, mut delete_batch: F) -> QueryResult<usize>
+where
+    F: FnMut(&mut PgConnection, i64) -> QueryResult<usize>,
+{
+    let mut removed = 0;
+    loop {
+        let deleted = delete_batch(conn, DELETE_BATCH_SIZE)?;
+        removed += deleted;
+
+        if (deleted as i64) < DELETE_BATCH_SIZE {
+            return Ok(removed);
+        }
+    }
+}
+
+// A LIKE pattern matching values that contain `text` as it is. Backslash, the default escape
+// character, and the wildcards `%` and `_` are escaped so they match only themselves.
+pub fn contains_pattern(text: &str) -> String {
+    let mut pattern = String::with_capacity(text.len() + 2);
+    pattern.push('%');
+    for c in text.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_wildcards_and_the_escape_character() {
+        assert_eq!(contains_pattern("ann"), "%ann%");
+        assert_eq!(contains_pattern("100%_off"), "%100\\%\\_off%");
+        assert_eq!(contains_pattern("a\\b"), "%a\\\\b%");
+    }
+}
@@ -1,5 +1,19 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    comments (id) {
+        id -> Uuid,
+        post_id -> Uuid,
+        author_id -> Nullable<Uuid>,
+        parent_id -> Nullable<Uuid>,
+        root_id -> Uuid,
+        body -> Text,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        deleted_at -> Nullable<Timestamptz>,
+    }
+}
+
 diesel::table! {
     oauth2_records (id) {
         id -> Uuid,
@@ -9,15 +23,94 @@ diesel::table! {
         pkce_code_verifier -> Varchar,
         #[max_length = 255]
         return_url -> Varchar,
+        created_at -> Timestamptz,
+        #[max_length = 64]
+        provider -> Varchar,
+        link_user_id -> Nullable<Uuid>,
     }
 }
 
 diesel::table! {
+    personal_access_tokens (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        #[max_length = 100]
+        name -> Varchar,
+        scopes -> Array<Text>,
+        #[max_length = 16]
+        token_prefix -> Varchar,
+        token_digest -> Text,
+        created_at -> Timestamptz,
+        expires_at -> Timestamptz,
+        last_used_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    post_slug_history (slug) {
+        slug -> Varchar,
+        post_id -> Uuid,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    post_tags (post_id, tag_id) {
+        post_id -> Uuid,
+        tag_id -> Uuid,
+    }
+}
+
+diesel::table! {
+    post_revisions (id) {
+        id -> Uuid,
+        post_id -> Uuid,
+        revision -> Int4,
+        title -> Varchar,
+        body -> Text,
+        editor_id -> Nullable<Uuid>,
+        restored_from -> Nullable<Int4>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use diesel_full_text_search::Tsvector;
+
     posts (id) {
         id -> Uuid,
         title -> Varchar,
         body -> Text,
         published -> Bool,
+        author_id -> Nullable<Uuid>,
+        created_at -> Timestamptz,
+        search_vector -> Tsvector,
+        updated_at -> Timestamptz,
+        published_at -> Nullable<Timestamptz>,
+        deleted_at -> Nullable<Timestamptz>,
+        publish_at -> Nullable<Timestamptz>,
+        slug -> Varchar,
+    }
+}
+
+diesel::table! {
+    tags (id) {
+        id -> Uuid,
+        name -> Varchar,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    user_identities (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        #[max_length = 64]
+        provider -> Varchar,
+        subject -> Text,
+        email -> Text,
+        created_at -> Timestamptz,
     }
 }
 
@@ -26,9 +119,17 @@ diesel::table! {
         id -> Uuid,
         user_id -> Uuid,
         session_token_p1 -> Text,
-        session_token_p2 -> Text,
+        session_token_p2_digest -> Text,
         created_at -> Int8,
         expires_at -> Int8,
+        oauth_access_token -> Nullable<Text>,
+        ip_address -> Nullable<Text>,
+        user_agent -> Nullable<Text>,
+        last_seen_at -> Int8,
+        previous_session_token_p2_digest -> Nullable<Text>,
+        rotated_at -> Nullable<Int8>,
+        #[max_length = 64]
+        oauth_provider -> Nullable<Varchar>,
     }
 }
 
@@ -36,12 +137,36 @@ diesel::table! {
     users (id) {
         id -> Uuid,
         email -> Text,
+        #[max_length = 16]
+        role -> Varchar,
+        created_at -> Timestamptz,
+        suspended_at -> Nullable<Timestamptz>,
     }
 }
 
+diesel::joinable!(comments -> posts (post_id));
+diesel::joinable!(comments -> users (author_id));
+diesel::joinable!(oauth2_records -> users (link_user_id));
+diesel::joinable!(personal_access_tokens -> users (user_id));
+diesel::joinable!(post_revisions -> posts (post_id));
+diesel::joinable!(post_revisions -> users (editor_id));
+diesel::joinable!(post_slug_history -> posts (post_id));
+diesel::joinable!(post_tags -> posts (post_id));
+diesel::joinable!(post_tags -> tags (tag_id));
+diesel::joinable!(posts -> users (author_id));
+diesel::joinable!(user_identities -> users (user_id));
+diesel::joinable!(user_sessions -> users (user_id));
+
 diesel::allow_tables_to_appear_in_same_query!(
+    comments,
     oauth2_records,
+    personal_access_tokens,
+    post_revisions,
+    post_slug_history,
+    post_tags,
     posts,
+    tags,
+    user_identities,
     user_sessions,
     users,
 );
@@ -1,10 +1,30 @@
+use crate::domain::problem::Problem;
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
 use deadpool_diesel::{InteractError, PoolError};
+use diesel::result::DatabaseErrorKind;
 use std::fmt;
+use tracing::log::{log, Level};
 
+// How long clients are asked to wait before retrying while the database is unavailable
+const UNAVAILABLE_RETRY_AFTER_SECS: u64 = 5;
+
+// Every variant but `NotFound` carries what went wrong underneath. It ends up in the log, never in
+// a response.
 #[derive(Debug)]
 pub enum InfraError {
-    InternalServerError,
+    InternalServerError(String),
     NotFound,
+    // A unique constraint, e.g. on users.email
+    Conflict(String),
+    ForeignKeyViolation(String),
+    CheckViolation(String),
+    // A concurrent transaction got in the way, trying again may well work
+    SerializationFailure(String),
+    // No connection could be had in time, or the database went away
+    Unavailable(String),
+    // The code run on a pooled connection panicked
+    InteractPanicked(String),
 }
 
 // Utility function to adapt errors of generic type T into InfraError
@@ -12,16 +32,105 @@ pub fn adapt_infra_error<T: Error>(error: T) -> InfraError {
     error.as_infra_error()
 }
 
-// Implement the Display trait to customize how InfraError is displayed
+impl InfraError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::InternalServerError(_) | Self::InteractPanicked(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            Self::NotFound => StatusCode::NOT_FOUND,
+            Self::Conflict(_) | Self::ForeignKeyViolation(_) | Self::SerializationFailure(_) => {
+                StatusCode::CONFLICT
+            }
+            Self::CheckViolation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InternalServerError(_) | Self::InteractPanicked(_) => "internal_error",
+            Self::NotFound => "not_found",
+            Self::Conflict(_) => "conflict",
+            Self::ForeignKeyViolation(_) => "foreign_key_violation",
+            Self::CheckViolation(_) => "check_violation",
+            Self::SerializationFailure(_) => "serialization_failure",
+            Self::Unavailable(_) => "unavailable",
+        }
+    }
+
+    // What went wrong underneath, for the log only
+    pub fn source(&self) -> Option<&str> {
+        match self {
+            Self::NotFound => None,
+            Self::InternalServerError(source)
+            | Self::Conflict(source)
+            | Self::ForeignKeyViolation(source)
+            | Self::CheckViolation(source)
+            | Self::SerializationFailure(source)
+            | Self::Unavailable(source)
+            | Self::InteractPanicked(source) => Some(source),
+        }
+    }
+
+    fn log_level(&self) -> Level {
+        match self {
+            Self::InternalServerError(_) | Self::InteractPanicked(_) => Level::Error,
+            Self::Unavailable(_) => Level::Warn,
+            Self::SerializationFailure(_) => Level::Info,
+            _ => Level::Debug,
+        }
+    }
+
+    // What clients get to read, which says nothing about tables, constraints or the database
+    pub fn message(&self) -> &'static str {
+        match self {
+            Self::InternalServerError(_) | Self::InteractPanicked(_) => "Internal server error",
+            Self::NotFound => "Not found",
+            Self::Conflict(_) => "This conflicts with data that already exists",
+            Self::ForeignKeyViolation(_) => "This refers to data that doesn't exist or is in use",
+            Self::CheckViolation(_) => "This breaks a rule the data has to follow",
+            Self::SerializationFailure(_) => {
+                "This clashed with a concurrent change, please try again"
+            }
+            Self::Unavailable(_) => "The service is unavailable, please try again later",
+        }
+    }
+}
+
+// Implement the Display trait to customize how InfraError is displayed. The source is included,
+// so this is for logs; responses are built from the message.
 impl fmt::Display for InfraError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            InfraError::NotFound => write!(f, "Not found"),
-            InfraError::InternalServerError => write!(f, "Internal server error"),
+        match self.source() {
+            Some(source) => write!(f, "{}: {}", self.message(), source),
+            None => write!(f, "{}", self.message()),
         }
     }
 }
 
+// Domain errors hand their `InfraError` over to this, so database failures are logged and
+// answered the same way wherever they happen
+impl IntoResponse for InfraError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let code = self.code();
+
+        log!(self.log_level(), "InfraError {}: {}", code, self);
+
+        let mut response = Problem::new(status, code, self.message()).into_response();
+
+        if let Self::Unavailable(_) = self {
+            response.headers_mut().insert(
+                header::RETRY_AFTER,
+                HeaderValue::from(UNAVAILABLE_RETRY_AFTER_SECS),
+            );
+        }
+
+        response
+    }
+}
+
 // Define a custom Error trait for types that can be converted to InfraError
 pub trait Error {
     fn as_infra_error(&self) -> InfraError;
@@ -32,7 +141,25 @@ impl Error for diesel::result::Error {
     fn as_infra_error(&self) -> InfraError {
         match self {
             diesel::result::Error::NotFound => InfraError::NotFound,
-            _ => InfraError::InternalServerError,
+            diesel::result::Error::DatabaseError(kind, info) => {
+                let source = match info.constraint_name() {
+                    Some(constraint) => format!("{} ({})", info.message(), constraint),
+                    None => info.message().to_string(),
+                };
+                match kind {
+                    DatabaseErrorKind::UniqueViolation => InfraError::Conflict(source),
+                    DatabaseErrorKind::ForeignKeyViolation => {
+                        InfraError::ForeignKeyViolation(source)
+                    }
+                    DatabaseErrorKind::CheckViolation => InfraError::CheckViolation(source),
+                    DatabaseErrorKind::SerializationFailure => {
+                        InfraError::SerializationFailure(source)
+                    }
+                    DatabaseErrorKind::ClosedConnection => InfraError::Unavailable(source),
+                    _ => InfraError::InternalServerError(source),
+                }
+            }
+            _ => InfraError::InternalServerError(self.to_string()),
         }
     }
 }
@@ -40,25 +167,42 @@ impl Error for diesel::result::Error {
 // Implement the Error trait for deadpool_diesel::PoolError
 impl Error for PoolError {
     fn as_infra_error(&self) -> InfraError {
-        InfraError::InternalServerError
+        match self {
+            // Waiting for a free connection, or on the database to hand out a new one, took too
+            // long, or the database can't be reached at all
+            PoolError::Timeout(_) | PoolError::Backend(_) | PoolError::Closed => {
+                InfraError::Unavailable(self.to_string())
+            }
+            _ => InfraError::InternalServerError(self.to_string()),
+        }
     }
 }
 
 // Implement the Error trait for InteractError
 impl Error for InteractError {
     fn as_infra_error(&self) -> InfraError {
-        InfraError::InternalServerError // Map all InteractError instances to InfraError::InternalServerError
+        match self {
+            InteractError::Panic(payload) => {
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .copied()
+                    .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                    .unwrap_or("unknown panic");
+                InfraError::InteractPanicked(message.to_string())
+            }
+            InteractError::Aborted => InfraError::InternalServerError(self.to_string()),
+        }
     }
 }
 
 impl Error for dotenvy::Error {
     fn as_infra_error(&self) -> InfraError {
-        InfraError::InternalServerError // Map all InteractError instances to InfraError::InternalServerError
+        InfraError::InternalServerError(self.to_string())
     }
 }
 
 impl Error for &str {
     fn as_infra_error(&self) -> InfraError {
-        InfraError::InternalServerError // Map all InteractError instances to InfraError::InternalServerError
+        InfraError::InternalServerError(self.to_string())
     }
 }
@@ -1,5 +1,7 @@
+use crate::infra::db::query::delete_in_batches;
 use crate::infra::db::schema::oauth2_records;
 use crate::infra::errors::{adapt_infra_error, InfraError};
+use chrono::{DateTime, Utc};
 use diesel::{
     ExpressionMethods, Insertable, QueryDsl, Queryable, RunQueryDsl, Selectable, SelectableHelper,
 };
@@ -15,6 +17,9 @@ pub struct Oauth2Record {
     pub csrf_state: String,
     pub pkce_code_verifier: String,
     pub return_url: String,
+    pub created_at: DateTime<Utc>,
+    pub provider: String,
+    pub link_user_id: Option<Uuid>,
 }
 
 #[derive(Deserialize, Insertable)]
@@ -23,6 +28,8 @@ pub struct NewOauth2Record {
     pub csrf_state: String,
     pub pkce_code_verifier: String,
     pub return_url: String,
+    pub provider: String,
+    pub link_user_id: Option<Uuid>,
 }
 
 pub async fn insert_oauth2_record(
@@ -50,7 +57,8 @@ pub async fn insert_oauth2_record(
 pub async fn delete_oauth2_record(
     pool: &deadpool_diesel::postgres::Pool,
     csrf_state: String,
-) -> Result<(String, String), InfraError> {
+    provider: String,
+) -> Result<Oauth2Record, InfraError> {
     debug!("->> {:<12} - delete_oauth2_record", "INFRASTRUCTURE");
 
     // Get a database connection from the pool and handle any potential errors
@@ -58,13 +66,51 @@ pub async fn delete_oauth2_record(
 
     let res = conn
         .interact(move |conn| {
-            diesel::delete(oauth2_records::table.filter(oauth2_records::csrf_state.eq(csrf_state)))
-                .returning(Oauth2Record::as_returning())
-                .get_result(conn)
+            // State handed out for one provider is no good for another
+            diesel::delete(
+                oauth2_records::table
+                    .filter(oauth2_records::csrf_state.eq(csrf_state))
+                    .filter(oauth2_records::provider.eq(provider)),
+            )
+            .returning(Oauth2Record::as_returning())
+            .get_result(conn)
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(res)
+}
+
+// Remove the records of logins that were started before `created_before` and never finished
+pub async fn delete_expired_oauth2_records(
+    pool: &deadpool_diesel::postgres::Pool,
+    created_before: DateTime<Utc>,
+) -> Result<usize, InfraError> {
+    debug!(
+        "->> {:<12} - delete_expired_oauth2_records",
+        "INFRASTRUCTURE"
+    );
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let res = conn
+        .interact(move |conn| {
+            delete_in_batches(conn, |conn, limit| {
+                let batch = oauth2_records::table
+                    .filter(oauth2_records::created_at.lt(created_before))
+                    .select(oauth2_records::id)
+                    .limit(limit)
+                    .load::<Uuid>(conn)?;
+
+                diesel::delete(oauth2_records::table.filter(oauth2_records::id.eq_any(batch)))
+                    .execute(conn)
+            })
         })
         .await
         .map_err(adapt_infra_error)?
         .map_err(adapt_infra_error)?;
 
-    Ok((res.pkce_code_verifier, res.return_url))
+    Ok(res)
 }
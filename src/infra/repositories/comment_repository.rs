@@ -0,0 +1,393 @@
+use crate::domain::models::comment::{CommentModel, CommentsPage};
+use crate::infra::db::schema::{comments, posts, users};
+use crate::infra::errors::{adapt_infra_error, InfraError};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use diesel::{
+    dsl::{self, exists},
+    BoolExpressionMethods, Connection, ExpressionMethods, Insertable, NullableExpressionMethods,
+    OptionalExtension, PgConnection, QueryDsl, QueryResult, Queryable, RunQueryDsl, Selectable,
+    SelectableHelper,
+};
+use serde::{Deserialize, Serialize};
+use tracing::log::debug;
+use uuid::Uuid;
+
+#[derive(Serialize, Queryable, Selectable)]
+#[diesel(table_name = comments)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct CommentDb {
+    pub id: Uuid,
+    pub post_id: Uuid,
+    pub author_id: Option<Uuid>,
+    pub parent_id: Option<Uuid>,
+    pub root_id: Uuid,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, Insertable)]
+#[diesel(table_name = comments)]
+pub struct NewCommentDb {
+    pub id: Uuid,
+    pub post_id: Uuid,
+    pub author_id: Uuid,
+    pub parent_id: Option<Uuid>,
+    pub root_id: Uuid,
+    pub body: String,
+}
+
+const DEFAULT_PAGE_SIZE: i64 = 20;
+const MAX_PAGE_SIZE: i64 = 100;
+
+// Pages are made of threads: `limit` counts top-level comments, each comes with all its replies
+#[derive(Deserialize)]
+pub struct CommentsFilter {
+    limit: Option<i64>,
+    cursor: Option<CommentsCursor>,
+}
+
+// Opaque keyset cursor: the position of the last top-level comment of a page, base64-encoded JSON
+#[derive(Deserialize)]
+#[serde(try_from = "String")]
+pub struct CommentsCursor(CursorKey);
+
+#[derive(Serialize, Deserialize)]
+struct CursorKey {
+    created_at: DateTime<Utc>,
+    id: Uuid,
+}
+
+impl CommentsCursor {
+    fn encode(comment_db: &CommentDb) -> String {
+        let key = CursorKey {
+            created_at: comment_db.created_at,
+            id: comment_db.id,
+        };
+
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(&key).unwrap_or_default())
+    }
+}
+
+impl TryFrom<String> for CommentsCursor {
+    type Error = &'static str;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(value)
+            .map_err(|_| "cursor is not valid base64")?;
+        let key = serde_json::from_slice(&bytes).map_err(|_| "cursor is malformed")?;
+
+        Ok(Self(key))
+    }
+}
+
+// A comment with the email of its author
+type CommentRow = (CommentDb, Option<String>);
+
+type LivePostQuery = dsl::Filter<
+    dsl::Filter<posts::table, dsl::Eq<posts::id, Uuid>>,
+    dsl::IsNull<posts::deleted_at>,
+>;
+
+pub async fn insert(
+    pool: &deadpool_diesel::postgres::Pool,
+    post_id: Uuid,
+    author_id: Uuid,
+    parent_id: Option<Uuid>,
+    body: String,
+) -> Result<CommentModel, InfraError> {
+    debug!("->> {:<12} - insert", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let (comment_db, author_email) = conn
+        .interact(move |conn| {
+            conn.transaction(|conn| {
+                let id = Uuid::new_v4();
+
+                // A reply joins the thread of its parent, which has to be on the same post;
+                // replying to a tombstone is fine, its thread is still there
+                let root_id = match parent_id {
+                    Some(parent_id) => comments::table
+                        .filter(comments::id.eq(parent_id))
+                        .filter(comments::post_id.eq(post_id))
+                        .select(comments::root_id)
+                        .get_result::<Uuid>(conn)?,
+                    None => id,
+                };
+
+                let new_comment = NewCommentDb {
+                    id,
+                    post_id,
+                    author_id,
+                    parent_id,
+                    root_id,
+                    body,
+                };
+
+                diesel::insert_into(comments::table)
+                    .values(new_comment)
+                    .execute(conn)?;
+
+                get_with_author(conn, id)
+            })
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(adapt_comment_db_to_comment(comment_db, author_email))
+}
+
+pub async fn get(
+    pool: &deadpool_diesel::postgres::Pool,
+    post_id: Uuid,
+    id: Uuid,
+) -> Result<CommentModel, InfraError> {
+    debug!("->> {:<12} - get", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    // Tombstones are only part of listings, on their own they don't exist anymore
+    let (comment_db, author_email) = conn
+        .interact(move |conn| {
+            comments::table
+                .left_join(users::table)
+                .filter(comments::id.eq(id))
+                .filter(comments::post_id.eq(post_id))
+                .filter(comments::deleted_at.is_null())
+                .select((CommentDb::as_select(), users::email.nullable()))
+                .get_result::<CommentRow>(conn)
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(adapt_comment_db_to_comment(comment_db, author_email))
+}
+
+pub async fn get_all(
+    pool: &deadpool_diesel::postgres::Pool,
+    post_id: Uuid,
+    filter: CommentsFilter,
+) -> Result<CommentsPage, InfraError> {
+    debug!("->> {:<12} - get_all", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let limit = filter
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    let (rows, next_cursor) = conn
+        .interact(move |conn| {
+            // Oldest threads come first, the way a conversation reads
+            let mut query = comments::table
+                .filter(comments::post_id.eq(post_id))
+                .filter(comments::parent_id.is_null())
+                .into_boxed();
+
+            if let Some(CommentsCursor(key)) = &filter.cursor {
+                query = query.filter(
+                    comments::created_at
+                        .gt(key.created_at)
+                        .or(comments::created_at
+                            .eq(key.created_at)
+                            .and(comments::id.gt(key.id))),
+                );
+            }
+
+            // Fetch one extra thread to learn whether there is another page
+            let mut roots = query
+                .order((comments::created_at.asc(), comments::id.asc()))
+                .limit(limit + 1)
+                .select(CommentDb::as_select())
+                .load::<CommentDb>(conn)?;
+
+            let next_cursor = if roots.len() as i64 > limit {
+                roots.truncate(limit as usize);
+                roots.last().map(CommentsCursor::encode)
+            } else {
+                None
+            };
+
+            let root_ids: Vec<Uuid> = roots.iter().map(|root| root.id).collect();
+
+            let rows = comments::table
+                .left_join(users::table)
+                .filter(comments::root_id.eq_any(root_ids))
+                .order((comments::created_at.asc(), comments::id.asc()))
+                .select((CommentDb::as_select(), users::email.nullable()))
+                .load::<CommentRow>(conn)?;
+
+            Ok::<_, diesel::result::Error>((rows, next_cursor))
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(CommentsPage {
+        comments: rows
+            .into_iter()
+            .map(|(comment_db, author_email)| adapt_comment_db_to_comment(comment_db, author_email))
+            .collect(),
+        next_cursor,
+    })
+}
+
+// Change the body of a comment, which only its author may do
+pub async fn update(
+    pool: &deadpool_diesel::postgres::Pool,
+    post_id: Uuid,
+    id: Uuid,
+    body: String,
+    author_id: Uuid,
+) -> Result<CommentModel, InfraError> {
+    debug!("->> {:<12} - update", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let (comment_db, author_email) = conn
+        .interact(move |conn| {
+            conn.transaction(|conn| {
+                diesel::update(
+                    comments::table
+                        .filter(comments::id.eq(id))
+                        .filter(comments::post_id.eq(post_id))
+                        .filter(comments::deleted_at.is_null())
+                        .filter(comments::author_id.eq(author_id))
+                        .filter(exists(live_post(post_id))),
+                )
+                .set(comments::body.eq(body))
+                .returning(comments::id)
+                .get_result::<Uuid>(conn)?;
+
+                get_with_author(conn, id)
+            })
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(adapt_comment_db_to_comment(comment_db, author_email))
+}
+
+// Delete a comment. One that still has replies is turned into a tombstone so the thread below it
+// survives, and the tombstone is returned; otherwise the row goes away for good, together with
+// any tombstones above it that were only kept around for its sake. Only the author of the comment
+// or of the post it's on may delete it.
+pub async fn delete(
+    pool: &deadpool_diesel::postgres::Pool,
+    post_id: Uuid,
+    id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<CommentModel>, InfraError> {
+    debug!("->> {:<12} - delete", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let res = conn
+        .interact(move |conn| {
+            conn.transaction(|conn| {
+                let parent_id = comments::table
+                    .filter(comments::id.eq(id))
+                    .filter(comments::post_id.eq(post_id))
+                    .filter(comments::deleted_at.is_null())
+                    .filter(exists(live_post(post_id)))
+                    .filter(comments::author_id.eq(user_id).or(exists(
+                        live_post(post_id).filter(posts::author_id.eq(user_id)),
+                    )))
+                    .select(comments::parent_id)
+                    .for_update()
+                    .get_result::<Option<Uuid>>(conn)?;
+
+                if has_replies(conn, id)? {
+                    diesel::update(comments::table.filter(comments::id.eq(id)))
+                        .set((comments::body.eq(""), comments::deleted_at.eq(Utc::now())))
+                        .execute(conn)?;
+
+                    return get_with_author(conn, id).map(Some);
+                }
+
+                diesel::delete(comments::table.filter(comments::id.eq(id))).execute(conn)?;
+
+                // Walk up the thread removing tombstones left without replies
+                let mut next = parent_id;
+                while let Some(ancestor_id) = next {
+                    let tombstone = comments::table
+                        .filter(comments::id.eq(ancestor_id))
+                        .filter(comments::deleted_at.is_not_null())
+                        .select(comments::parent_id)
+                        .for_update()
+                        .get_result::<Option<Uuid>>(conn)
+                        .optional()?;
+
+                    let Some(parent_id) = tombstone else {
+                        break;
+                    };
+
+                    if has_replies(conn, ancestor_id)? {
+                        break;
+                    }
+
+                    diesel::delete(comments::table.filter(comments::id.eq(ancestor_id)))
+                        .execute(conn)?;
+
+                    next = parent_id;
+                }
+
+                Ok(None)
+            })
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(res.map(|(comment_db, author_email)| adapt_comment_db_to_comment(comment_db, author_email)))
+}
+
+// The post comments are on, as long as it isn't in the trash
+fn live_post(post_id: Uuid) -> LivePostQuery {
+    posts::table
+        .filter(posts::id.eq(post_id))
+        .filter(posts::deleted_at.is_null())
+}
+
+fn has_replies(conn: &mut PgConnection, id: Uuid) -> QueryResult<bool> {
+    diesel::select(exists(comments::table.filter(comments::parent_id.eq(id)))).get_result(conn)
+}
+
+fn get_with_author(conn: &mut PgConnection, id: Uuid) -> QueryResult<CommentRow> {
+    comments::table
+        .left_join(users::table)
+        .filter(comments::id.eq(id))
+        .select((CommentDb::as_select(), users::email.nullable()))
+        .get_result(conn)
+}
+
+fn adapt_comment_db_to_comment(
+    comment_db: CommentDb,
+    author_email: Option<String>,
+) -> CommentModel {
+    CommentModel {
+        id: comment_db.id,
+        post_id: comment_db.post_id,
+        author_id: comment_db.author_id,
+        author_email,
+        parent_id: comment_db.parent_id,
+        root_id: comment_db.root_id,
+        body: comment_db.body,
+        created_at: comment_db.created_at,
+        updated_at: comment_db.updated_at,
+        deleted_at: comment_db.deleted_at,
+    }
+}
@@ -1,4 +1,9 @@
 pub mod auth_repository;
+pub mod comment_repository;
+pub mod personal_access_tokens_repository;
 pub mod post_repository;
+pub mod post_revision_repository;
+pub mod tag_repository;
+pub mod user_identities_repository;
 pub mod user_repository;
 pub mod user_sessions_repository;
@@ -0,0 +1,242 @@
+use crate::domain::models::personal_access_token::{PersonalAccessTokenModel, TokenScope};
+use crate::infra::db::query::delete_in_batches;
+use crate::infra::db::schema::personal_access_tokens;
+use crate::infra::errors::{adapt_infra_error, InfraError};
+use chrono::{DateTime, Utc};
+use diesel::{
+    ExpressionMethods, Insertable, QueryDsl, Queryable, RunQueryDsl, Selectable, SelectableHelper,
+};
+use serde::{Deserialize, Serialize};
+use tracing::log::debug;
+use uuid::Uuid;
+
+#[derive(Serialize, Queryable, Selectable)]
+#[diesel(table_name = personal_access_tokens)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PersonalAccessTokenDb {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub token_prefix: String,
+    pub token_digest: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, Insertable)]
+#[diesel(table_name = personal_access_tokens)]
+pub struct NewPersonalAccessTokenDb {
+    pub user_id: Uuid,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub token_prefix: String,
+    pub token_digest: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+pub async fn insert(
+    pool: &deadpool_diesel::postgres::Pool,
+    new_token: NewPersonalAccessTokenDb,
+) -> Result<PersonalAccessTokenModel, InfraError> {
+    debug!("->> {:<12} - insert", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let res = conn
+        .interact(|conn| {
+            diesel::insert_into(personal_access_tokens::table)
+                .values(new_token)
+                .returning(PersonalAccessTokenDb::as_returning())
+                .get_result(conn)
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(adapt_personal_access_token_db_to_personal_access_token(res))
+}
+
+// Find the token whose digest this is; expired tokens are not found
+pub async fn get_by_digest(
+    pool: &deadpool_diesel::postgres::Pool,
+    token_digest: String,
+    now: DateTime<Utc>,
+) -> Result<PersonalAccessTokenModel, InfraError> {
+    debug!("->> {:<12} - get_by_digest", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let res = conn
+        .interact(move |conn| {
+            personal_access_tokens::table
+                .filter(personal_access_tokens::token_digest.eq(token_digest))
+                .filter(personal_access_tokens::expires_at.gt(now))
+                .select(PersonalAccessTokenDb::as_select())
+                .get_result(conn)
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(adapt_personal_access_token_db_to_personal_access_token(res))
+}
+
+// The tokens of a user that haven't expired yet, newest first
+pub async fn get_all_by_user(
+    pool: &deadpool_diesel::postgres::Pool,
+    user_id: Uuid,
+    now: DateTime<Utc>,
+) -> Result<Vec<PersonalAccessTokenModel>, InfraError> {
+    debug!("->> {:<12} - get_all_by_user", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let res = conn
+        .interact(move |conn| {
+            personal_access_tokens::table
+                .filter(personal_access_tokens::user_id.eq(user_id))
+                .filter(personal_access_tokens::expires_at.gt(now))
+                .order(personal_access_tokens::created_at.desc())
+                .select(PersonalAccessTokenDb::as_select())
+                .load::<PersonalAccessTokenDb>(conn)
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(res
+        .into_iter()
+        .map(adapt_personal_access_token_db_to_personal_access_token)
+        .collect())
+}
+
+// Record that a token was just used
+pub async fn touch(
+    pool: &deadpool_diesel::postgres::Pool,
+    id: Uuid,
+    now: DateTime<Utc>,
+) -> Result<(), InfraError> {
+    debug!("->> {:<12} - touch", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    conn.interact(move |conn| {
+        diesel::update(personal_access_tokens::table.filter(personal_access_tokens::id.eq(id)))
+            .set(personal_access_tokens::last_used_at.eq(now))
+            .execute(conn)
+    })
+    .await
+    .map_err(adapt_infra_error)?
+    .map_err(adapt_infra_error)?;
+
+    Ok(())
+}
+
+// Remove one of the tokens of a user; tokens of anyone else are not found
+pub async fn delete_by_user(
+    pool: &deadpool_diesel::postgres::Pool,
+    id: Uuid,
+    user_id: Uuid,
+) -> Result<(), InfraError> {
+    debug!("->> {:<12} - delete_by_user", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    conn.interact(move |conn| {
+        diesel::delete(
+            personal_access_tokens::table
+                .filter(personal_access_tokens::id.eq(id))
+                .filter(personal_access_tokens::user_id.eq(user_id)),
+        )
+        .returning(personal_access_tokens::id)
+        .get_result::<Uuid>(conn)
+    })
+    .await
+    .map_err(adapt_infra_error)?
+    .map_err(adapt_infra_error)?;
+
+    Ok(())
+}
+
+// Remove every token of a user, returning how many were removed
+pub async fn delete_all_by_user(
+    pool: &deadpool_diesel::postgres::Pool,
+    user_id: Uuid,
+) -> Result<usize, InfraError> {
+    debug!("->> {:<12} - delete_all_by_user", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let res = conn
+        .interact(move |conn| {
+            diesel::delete(
+                personal_access_tokens::table.filter(personal_access_tokens::user_id.eq(user_id)),
+            )
+            .execute(conn)
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(res)
+}
+
+// Remove tokens that expired before `now`
+pub async fn delete_expired(
+    pool: &deadpool_diesel::postgres::Pool,
+    now: DateTime<Utc>,
+) -> Result<usize, InfraError> {
+    debug!("->> {:<12} - delete_expired", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let res = conn
+        .interact(move |conn| {
+            delete_in_batches(conn, |conn, limit| {
+                let batch = personal_access_tokens::table
+                    .filter(personal_access_tokens::expires_at.le(now))
+                    .select(personal_access_tokens::id)
+                    .limit(limit)
+                    .load::<Uuid>(conn)?;
+
+                diesel::delete(
+                    personal_access_tokens::table.filter(personal_access_tokens::id.eq_any(batch)),
+                )
+                .execute(conn)
+            })
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(res)
+}
+
+fn adapt_personal_access_token_db_to_personal_access_token(
+    token_db: PersonalAccessTokenDb,
+) -> PersonalAccessTokenModel {
+    PersonalAccessTokenModel {
+        id: token_db.id,
+        user_id: token_db.user_id,
+        name: token_db.name,
+        // Scopes this version doesn't know about grant nothing
+        scopes: token_db
+            .scopes
+            .iter()
+            .filter_map(|scope| TokenScope::parse(scope))
+            .collect(),
+        token_prefix: token_db.token_prefix,
+        created_at: token_db.created_at,
+        expires_at: token_db.expires_at,
+        last_used_at: token_db.last_used_at,
+    }
+}
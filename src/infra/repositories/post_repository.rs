@@ -1,14 +1,28 @@
-use crate::domain::models::post::PostModel;
-use crate::handlers::posts::UpdatePostRequest;
+use crate::domain::models::post::{PostModel, PostsPage};
+use crate::handlers::posts::{slugify, UpdatePostRequest};
 use crate::infra::{
-    db::schema::posts,
+    db::query::{contains_pattern, delete_in_batches},
+    db::schema::{post_slug_history, post_tags, posts, tags, users},
     errors::{adapt_infra_error, InfraError},
+    repositories::post_revision_repository,
 };
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
 use diesel::{
-    AsChangeset, ExpressionMethods, Insertable, PgTextExpressionMethods, QueryDsl, Queryable,
-    RunQueryDsl, Selectable, SelectableHelper,
+    dsl::{count_star, IntoBoxed, LeftJoin},
+    expression::{AsExpression, BoxableExpression},
+    pg::Pg,
+    sql_types::{Bool, Nullable},
+    AsChangeset, BoolExpressionMethods, Connection, ExpressionMethods, Insertable,
+    NullableExpressionMethods, OptionalExtension, PgConnection, PgTextExpressionMethods, QueryDsl,
+    QueryResult, Queryable, RunQueryDsl, Selectable, SelectableHelper, TextExpressionMethods,
+};
+use diesel_full_text_search::{
+    configuration::TsConfigurationByName, to_tsquery_with_search_config,
+    ts_headline_with_search_config, ts_rank, TsVectorExtensions,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use tracing::log::debug;
 use uuid::Uuid;
 
@@ -20,52 +34,210 @@ pub struct PostDb {
     pub title: String,
     pub body: String,
     pub published: bool,
+    pub author_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub published_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub publish_at: Option<DateTime<Utc>>,
+    pub slug: String,
 }
 
-#[derive(Deserialize, Insertable)]
+#[derive(Clone, Deserialize, Insertable)]
 #[diesel(table_name = posts)] // Use the 'posts' table
 pub struct NewPostDb {
     pub title: String,
     pub body: String,
     pub published: bool,
+    pub author_id: Uuid,
+    pub slug: String,
 }
 
+const DEFAULT_PAGE_SIZE: i64 = 20;
+const MAX_PAGE_SIZE: i64 = 100;
+const PUBLISH_BATCH_SIZE: i64 = 100;
+
+// Must match the configuration the `search_vector` column is generated with
+const SEARCH_CONFIG: TsConfigurationByName = TsConfigurationByName("english");
+
 #[derive(Deserialize)]
 pub struct PostsFilter {
     published: Option<bool>,
     title_contains: Option<String>,
+    q: Option<String>,
+    author_id: Option<Uuid>,
+    tags: Option<String>,
+    tags_match: Option<TagsMatch>,
+    created_after: Option<DateTime<Utc>>,
+    created_before: Option<DateTime<Utc>>,
+    updated_after: Option<DateTime<Utc>>,
+    updated_before: Option<DateTime<Utc>>,
+    published_after: Option<DateTime<Utc>>,
+    published_before: Option<DateTime<Utc>>,
+    limit: Option<i64>,
+    cursor: Option<PostsCursor>,
+    sort: Option<PostsSort>,
+    include_total: Option<bool>,
+}
+
+impl PostsFilter {
+    // A cursor only makes sense for the sort order it was issued for
+    pub fn cursor_matches_sort(&self) -> bool {
+        match &self.cursor {
+            Some(cursor) => cursor.0.sort == self.effective_sort(),
+            None => true,
+        }
+    }
+
+    fn tsquery(&self) -> Option<String> {
+        self.q.as_deref().and_then(build_tsquery)
+    }
+
+    // Searches are ordered by relevance unless asked otherwise; without a search there is no rank
+    fn effective_sort(&self) -> PostsSort {
+        let searching = self.tsquery().is_some();
+
+        match self.sort {
+            Some(PostsSort::Relevance) if !searching => PostsSort::default(),
+            Some(sort) => sort,
+            None if searching => PostsSort::Relevance,
+            None => PostsSort::default(),
+        }
+    }
+}
+
+// Whether a post needs any or all of the tags asked for in `PostsFilter::tags`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TagsMatch {
+    #[default]
+    Any,
+    All,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PostsSort {
+    #[default]
+    CreatedAtDesc,
+    CreatedAtAsc,
+    TitleAsc,
+    TitleDesc,
+    Relevance,
+}
+
+// Opaque keyset cursor: the sort key of the last row of a page, base64-encoded JSON
+#[derive(Deserialize)]
+#[serde(try_from = "String")]
+pub struct PostsCursor(CursorKey);
+
+#[derive(Serialize, Deserialize)]
+struct CursorKey {
+    sort: PostsSort,
+    created_at: DateTime<Utc>,
+    title: String,
+    #[serde(default)]
+    rank: f32,
+    id: Uuid,
+}
+
+impl PostsCursor {
+    fn encode(sort: PostsSort, post_db: &PostDb, rank: f32) -> String {
+        let key = CursorKey {
+            sort,
+            created_at: post_db.created_at,
+            title: post_db.title.clone(),
+            rank,
+            id: post_db.id,
+        };
+
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(&key).unwrap_or_default())
+    }
 }
 
+impl TryFrom<String> for PostsCursor {
+    type Error = &'static str;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(value)
+            .map_err(|_| "cursor is not valid base64")?;
+        let key = serde_json::from_slice(&bytes).map_err(|_| "cursor is malformed")?;
+
+        Ok(Self(key))
+    }
+}
+
+type PostsWithAuthorQuery = IntoBoxed<'static, LeftJoin<posts::table, users::table>, Pg>;
+
+// A condition on the posts table, e.g. who wrote them
+type PostPredicate = Box<dyn BoxableExpression<posts::table, Pg, SqlType = Nullable<Bool>>>;
+
+// A post with the email of its author and its tag names
+type PostRow = (PostDb, Option<String>, Vec<String>);
+
 #[derive(AsChangeset)]
 #[diesel(table_name = posts)]
 struct UpdatePostChangeset {
     title: Option<String>,
     body: Option<String>,
     published: Option<bool>,
+    published_at: Option<Option<DateTime<Utc>>>,
+    publish_at: Option<Option<DateTime<Utc>>>,
+    slug: Option<String>,
+}
+
+impl UpdatePostChangeset {
+    // Diesel refuses to run an UPDATE without anything to set
+    fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.body.is_none()
+            && self.published.is_none()
+            && self.published_at.is_none()
+            && self.publish_at.is_none()
+            && self.slug.is_none()
+    }
 }
 
 pub async fn insert(
     pool: &deadpool_diesel::postgres::Pool,
-    new_post: NewPostDb,
+    mut new_post: NewPostDb,
+    tags: Vec<String>,
+    suffix_slug: bool,
 ) -> Result<PostModel, InfraError> {
     debug!("->> {:<12} - insert", "INFRASTRUCTURE");
 
     // Get a database connection from the pool and handle any potential errors
     let conn = pool.get().await.map_err(adapt_infra_error)?;
 
-    // Insert the new post into the 'posts' table returning the inserted post
-    let res = conn
-        .interact(|conn| {
-            diesel::insert_into(posts::table)
-                .values(new_post)
-                .returning(PostDb::as_returning())
-                .get_result(conn)
+    // Insert the new post into the 'posts' table and load it back together with its author
+    let (post_db, author_email, tags) = conn
+        .interact(move |conn| {
+            conn.transaction(|conn| {
+                // Suffix the slug when another post already has it, unless it was picked by hand
+                if suffix_slug {
+                    new_post.slug = unique_slug(conn, &new_post.slug)?;
+                }
+
+                let author_id = new_post.author_id;
+                let id = diesel::insert_into(posts::table)
+                    .values(new_post)
+                    .returning(posts::id)
+                    .get_result(conn)?;
+
+                // The first revision is the post as it was created
+                post_revision_repository::record(conn, id, Some(author_id), None)?;
+
+                set_tags(conn, id, &tags)?;
+
+                get_with_author(conn, id)
+            })
         })
         .await
         .map_err(adapt_infra_error)?
         .map_err(adapt_infra_error)?;
 
-    Ok(adapt_post_db_to_post(res))
+    Ok(adapt_post_db_to_post(post_db, author_email, tags))
 }
 
 pub async fn get(
@@ -77,118 +249,1051 @@ pub async fn get(
     // Get a database connection from the pool and handle any potential errors
     let conn = pool.get().await.map_err(adapt_infra_error)?;
 
-    // Query the 'posts' table to retrieve the post by its ID
-    let res = conn
+    // Query the 'posts' table to retrieve the post by its ID, unless it's in the trash
+    let (post_db, author_email, tags) = conn
         .interact(move |conn| {
             posts::table
+                .left_join(users::table)
                 .filter(posts::id.eq(id))
-                .select(PostDb::as_select()) // Select the post
+                .filter(posts::deleted_at.is_null())
+                .select((PostDb::as_select(), users::email.nullable()))
                 .get_result(conn)
+                .and_then(|row| with_tags(conn, row))
         })
         .await
         .map_err(adapt_infra_error)?
         .map_err(adapt_infra_error)?;
 
     // Adapt the database representation to the application's domain model
-    Ok(adapt_post_db_to_post(res))
+    Ok(adapt_post_db_to_post(post_db, author_email, tags))
+}
+
+pub async fn get_by_slug(
+    pool: &deadpool_diesel::postgres::Pool,
+    slug: String,
+) -> Result<PostModel, InfraError> {
+    debug!("->> {:<12} - get_by_slug", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let (post_db, author_email, tags) = conn
+        .interact(move |conn| {
+            posts::table
+                .left_join(users::table)
+                .filter(posts::slug.eq(slug))
+                .filter(posts::deleted_at.is_null())
+                .select((PostDb::as_select(), users::email.nullable()))
+                .get_result(conn)
+                .and_then(|row| with_tags(conn, row))
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(adapt_post_db_to_post(post_db, author_email, tags))
+}
+
+// Look up a slug a post used to have and return the one it goes by now
+pub async fn get_current_slug(
+    pool: &deadpool_diesel::postgres::Pool,
+    old_slug: String,
+) -> Result<String, InfraError> {
+    debug!("->> {:<12} - get_current_slug", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let res = conn
+        .interact(move |conn| {
+            post_slug_history::table
+                .inner_join(posts::table)
+                .filter(post_slug_history::slug.eq(old_slug))
+                .filter(posts::deleted_at.is_null())
+                .select(posts::slug)
+                .get_result::<String>(conn)
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(res)
+}
+
+// A slug is free when no other post uses it, now or in its history
+pub async fn is_slug_available(
+    pool: &deadpool_diesel::postgres::Pool,
+    slug: String,
+    post_id: Option<Uuid>,
+) -> Result<bool, InfraError> {
+    debug!("->> {:<12} - is_slug_available", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let owner = conn
+        .interact(move |conn| {
+            let current = posts::table
+                .filter(posts::slug.eq(&slug))
+                .select(posts::id)
+                .get_result::<Uuid>(conn)
+                .optional()?;
+
+            match current {
+                Some(id) => Ok(Some(id)),
+                None => post_slug_history::table
+                    .filter(post_slug_history::slug.eq(&slug))
+                    .select(post_slug_history::post_id)
+                    .get_result::<Uuid>(conn)
+                    .optional(),
+            }
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(owner.is_none() || owner == post_id)
 }
 
 pub async fn get_all(
     pool: &deadpool_diesel::postgres::Pool,
     filter: PostsFilter,
-) -> Result<Vec<PostModel>, InfraError> {
+) -> Result<PostsPage, InfraError> {
     debug!("->> {:<12} - get_all", "INFRASTRUCTURE");
 
     // Get a database connection from the pool and handle any potential errors
     let conn = pool.get().await.map_err(adapt_infra_error)?;
 
-    // Build a dynamic query for retrieving posts
-    let res = conn
+    let sort = filter.effective_sort();
+    let tsquery = filter.tsquery();
+    let limit = filter
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    // Build a dynamic query for retrieving one page of posts
+    let (mut res, mut tags, total) = conn
         .interact(move |conn| {
-            let mut query = posts::table.into_boxed::<diesel::pg::Pg>();
+            // Count every matching post, ignoring the cursor, only when asked to
+            let total = match filter.include_total {
+                Some(true) => Some(filtered_posts(&filter).count().get_result::<i64>(conn)?),
+                _ => None,
+            };
 
-            // Apply filtering conditions if provided
-            if let Some(published) = filter.published {
-                query = query.filter(posts::published.eq(published));
-            }
+            let mut query = filtered_posts(&filter);
 
-            if let Some(title_contains) = filter.title_contains {
-                query = query.filter(posts::title.ilike(format!("%{}%", title_contains)));
+            // Continue strictly after the last row of the previous page
+            if let Some(PostsCursor(key)) = &filter.cursor {
+                query = match key.sort {
+                    PostsSort::CreatedAtDesc => query.filter(
+                        posts::created_at.lt(key.created_at).or(posts::created_at
+                            .eq(key.created_at)
+                            .and(posts::id.lt(key.id))),
+                    ),
+                    PostsSort::CreatedAtAsc => query.filter(
+                        posts::created_at.gt(key.created_at).or(posts::created_at
+                            .eq(key.created_at)
+                            .and(posts::id.gt(key.id))),
+                    ),
+                    PostsSort::TitleAsc => query.filter(
+                        posts::title
+                            .gt(key.title.clone())
+                            .or(posts::title.eq(key.title.clone()).and(posts::id.gt(key.id))),
+                    ),
+                    PostsSort::TitleDesc => query.filter(
+                        posts::title
+                            .lt(key.title.clone())
+                            .or(posts::title.eq(key.title.clone()).and(posts::id.lt(key.id))),
+                    ),
+                    PostsSort::Relevance => match &tsquery {
+                        Some(tsquery) => {
+                            let rank = || ts_rank(posts::search_vector, parse_tsquery(tsquery));
+                            query.filter(
+                                rank()
+                                    .lt(key.rank)
+                                    .or(rank().eq(key.rank).and(posts::id.lt(key.id))),
+                            )
+                        }
+                        None => query,
+                    },
+                };
             }
 
-            // Select the posts matching the query
-            query.select(PostDb::as_select()).load::<PostDb>(conn)
+            // The id breaks ties so that the order is total and the cursor stays stable
+            query = match (sort, &tsquery) {
+                (PostsSort::CreatedAtDesc, _) => {
+                    query.order((posts::created_at.desc(), posts::id.desc()))
+                }
+                (PostsSort::CreatedAtAsc, _) => {
+                    query.order((posts::created_at.asc(), posts::id.asc()))
+                }
+                (PostsSort::TitleAsc, _) => query.order((posts::title.asc(), posts::id.asc())),
+                (PostsSort::TitleDesc, _) => query.order((posts::title.desc(), posts::id.desc())),
+                (PostsSort::Relevance, Some(tsquery)) => query.order((
+                    ts_rank(posts::search_vector, parse_tsquery(tsquery)).desc(),
+                    posts::id.desc(),
+                )),
+                (PostsSort::Relevance, None) => query,
+            };
+
+            // Fetch one extra row to find out whether there is a next page
+            let query = query.limit(limit + 1);
+
+            // A search also brings back each hit's rank and a highlighted snippet of its body
+            let res = match &tsquery {
+                Some(tsquery) => query
+                    .select((
+                        PostDb::as_select(),
+                        users::email.nullable(),
+                        ts_headline_with_search_config(
+                            SEARCH_CONFIG,
+                            posts::body,
+                            parse_tsquery(tsquery),
+                        )
+                        .nullable(),
+                        ts_rank(posts::search_vector, parse_tsquery(tsquery)),
+                    ))
+                    .load::<(PostDb, Option<String>, Option<String>, f32)>(conn)?,
+                None => query
+                    .select((PostDb::as_select(), users::email.nullable()))
+                    .load::<(PostDb, Option<String>)>(conn)?
+                    .into_iter()
+                    .map(|(post_db, author_email)| (post_db, author_email, None, 0.0))
+                    .collect(),
+            };
+
+            // The tags of the whole page come with a single extra query
+            let ids: Vec<Uuid> = res.iter().map(|(post_db, ..)| post_db.id).collect();
+            let tags = load_tags(conn, &ids)?;
+
+            Ok::<_, diesel::result::Error>((res, tags, total))
         })
         .await
         .map_err(adapt_infra_error)?
         .map_err(adapt_infra_error)?;
 
-    // Adapt the database representations to the application's domain models
-    let posts: Vec<PostModel> = res
-        .into_iter()
-        .map(|post_db| adapt_post_db_to_post(post_db))
-        .collect();
+    let next_cursor = if res.len() as i64 > limit {
+        res.truncate(limit as usize);
+        res.last()
+            .map(|(post_db, _, _, rank)| PostsCursor::encode(sort, post_db, *rank))
+    } else {
+        None
+    };
+
+    // Keep the snippets aside, keyed by post, so the posts themselves stay plain domain models
+    let mut snippets = HashMap::new();
+    let mut posts = Vec::with_capacity(res.len());
 
-    Ok(posts)
+    for (post_db, author_email, snippet, _) in res {
+        if let Some(snippet) = snippet {
+            snippets.insert(post_db.id, snippet);
+        }
+        let post_tags = tags.remove(&post_db.id).unwrap_or_default();
+        posts.push(adapt_post_db_to_post(post_db, author_email, post_tags));
+    }
+
+    Ok(PostsPage {
+        posts,
+        snippets,
+        next_cursor,
+        total,
+    })
 }
 
 pub async fn update(
     pool: &deadpool_diesel::postgres::Pool,
     id: Uuid,
     updated_post: UpdatePostRequest,
+    editor_id: Uuid,
+    author_id: Option<Uuid>,
 ) -> Result<PostModel, InfraError> {
     debug!("->> {:<12} - update", "INFRASTRUCTURE");
 
     // Get a database connection from the pool and handle any potential errors
     let conn = pool.get().await.map_err(adapt_infra_error)?;
 
-    let changeset = UpdatePostChangeset {
+    let mut changeset = UpdatePostChangeset {
         title: updated_post.title,
         body: updated_post.body,
         published: updated_post.published,
+        published_at: None,
+        publish_at: None,
+        slug: updated_post.slug,
     };
+    let tags = updated_post.tags;
 
-    let res = conn
+    let (post_db, author_email, tags) = conn
         .interact(move |conn| {
-            diesel::update(posts::table.filter(posts::id.eq(id)))
-                .set(&changeset)
-                .returning(PostDb::as_returning())
-                .get_result(conn)
+            conn.transaction(|conn| {
+                // Lock the row so that concurrent updates agree on whether this one publishes it
+                let (published, slug, title, body) = posts::table
+                    .filter(posts::id.eq(id))
+                    .filter(posts::deleted_at.is_null())
+                    .filter(written_by(author_id))
+                    .select((posts::published, posts::slug, posts::title, posts::body))
+                    .for_update()
+                    .get_result::<(bool, String, String, String)>(conn)?;
+
+                // Content sent as it already is doesn't make a new revision
+                if changeset.title.as_ref() == Some(&title) {
+                    changeset.title = None;
+                }
+                if changeset.body.as_ref() == Some(&body) {
+                    changeset.body = None;
+                }
+                let content_changed = changeset.title.is_some() || changeset.body.is_some();
+
+                // Stamp the moment a post goes live and forget it once it's taken down
+                changeset.published_at = match (published, changeset.published) {
+                    (false, Some(true)) => Some(Some(Utc::now())),
+                    (true, Some(false)) => Some(None),
+                    _ => None,
+                };
+
+                // Publishing by hand supersedes any pending schedule
+                if changeset.published == Some(true) {
+                    changeset.publish_at = Some(None);
+                }
+
+                // Remember the old slug so links to it keep working, and take the new one
+                // back out of the history in case the post is returning to it
+                match &changeset.slug {
+                    Some(new_slug) if *new_slug != slug => {
+                        diesel::insert_into(post_slug_history::table)
+                            .values((
+                                post_slug_history::slug.eq(&slug),
+                                post_slug_history::post_id.eq(id),
+                            ))
+                            .on_conflict_do_nothing()
+                            .execute(conn)?;
+
+                        diesel::delete(
+                            post_slug_history::table
+                                .filter(post_slug_history::slug.eq(new_slug))
+                                .filter(post_slug_history::post_id.eq(id)),
+                        )
+                        .execute(conn)?;
+                    }
+                    _ => changeset.slug = None,
+                }
+
+                // Only the tags may have changed, or nothing at all
+                if !changeset.is_empty() {
+                    diesel::update(posts::table.filter(posts::id.eq(id)))
+                        .set(&changeset)
+                        .execute(conn)?;
+                }
+
+                // Keep the edited content as the new head revision
+                if content_changed {
+                    post_revision_repository::record(conn, id, Some(editor_id), None)?;
+                }
+
+                if let Some(tags) = &tags {
+                    set_tags(conn, id, tags)?;
+                }
+
+                get_with_author(conn, id)
+            })
         })
         .await
         .map_err(adapt_infra_error)?
         .map_err(adapt_infra_error)?;
 
-    Ok(adapt_post_db_to_post(res))
+    Ok(adapt_post_db_to_post(post_db, author_email, tags))
+}
+
+pub async fn restore_revision(
+    pool: &deadpool_diesel::postgres::Pool,
+    id: Uuid,
+    revision: i32,
+    editor_id: Uuid,
+    author_id: Option<Uuid>,
+) -> Result<PostModel, InfraError> {
+    debug!("->> {:<12} - restore_revision", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    // Copy the old content back onto the post and record it as a new head revision,
+    // so restoring never rewrites history
+    let (post_db, author_email, tags) = conn
+        .interact(move |conn| {
+            conn.transaction(|conn| {
+                posts::table
+                    .filter(posts::id.eq(id))
+                    .filter(posts::deleted_at.is_null())
+                    .filter(written_by(author_id))
+                    .select(posts::id)
+                    .for_update()
+                    .get_result::<Uuid>(conn)?;
+
+                let old = post_revision_repository::find(conn, id, revision)?;
+
+                diesel::update(posts::table.filter(posts::id.eq(id)))
+                    .set((posts::title.eq(old.title), posts::body.eq(old.body)))
+                    .execute(conn)?;
+
+                post_revision_repository::record(conn, id, Some(editor_id), Some(revision))?;
+
+                get_with_author(conn, id)
+            })
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(adapt_post_db_to_post(post_db, author_email, tags))
 }
 
 pub async fn delete(
     pool: &deadpool_diesel::postgres::Pool,
     id: Uuid,
+    author_id: Option<Uuid>,
 ) -> Result<PostModel, InfraError> {
     debug!("->> {:<12} - delete", "INFRASTRUCTURE");
 
     // Get a database connection from the pool and handle any potential errors
     let conn = pool.get().await.map_err(adapt_infra_error)?;
 
-    let res = conn
+    // Move the post to the trash; it's only removed for good by the purge
+    let (post_db, author_email, tags) = conn
         .interact(move |conn| {
-            diesel::delete(posts::table.filter(posts::id.eq(id)))
-                .returning(PostDb::as_returning())
+            conn.transaction(|conn| {
+                diesel::update(
+                    posts::table
+                        .filter(posts::id.eq(id))
+                        .filter(posts::deleted_at.is_null())
+                        .filter(written_by(author_id)),
+                )
+                .set(posts::deleted_at.eq(Utc::now()))
+                .returning(posts::id)
+                .get_result::<Uuid>(conn)?;
+
+                get_with_author(conn, id)
+            })
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(adapt_post_db_to_post(post_db, author_email, tags))
+}
+
+pub async fn get_trashed(
+    pool: &deadpool_diesel::postgres::Pool,
+    id: Uuid,
+) -> Result<PostModel, InfraError> {
+    debug!("->> {:<12} - get_trashed", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let (post_db, author_email, tags) = conn
+        .interact(move |conn| {
+            posts::table
+                .left_join(users::table)
+                .filter(posts::id.eq(id))
+                .filter(posts::deleted_at.is_not_null())
+                .select((PostDb::as_select(), users::email.nullable()))
                 .get_result(conn)
+                .and_then(|row| with_tags(conn, row))
         })
         .await
         .map_err(adapt_infra_error)?
         .map_err(adapt_infra_error)?;
 
-    Ok(adapt_post_db_to_post(res))
+    Ok(adapt_post_db_to_post(post_db, author_email, tags))
 }
 
-fn adapt_post_db_to_post(post_db: PostDb) -> PostModel {
+pub async fn get_trash(
+    pool: &deadpool_diesel::postgres::Pool,
+    author_id: Option<Uuid>,
+) -> Result<Vec<PostModel>, InfraError> {
+    debug!("->> {:<12} - get_trash", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    // Most recently trashed posts come first
+    let res = conn
+        .interact(move |conn| {
+            let mut query: PostsWithAuthorQuery = posts::table
+                .left_join(users::table)
+                .filter(posts::deleted_at.is_not_null())
+                .into_boxed();
+            if let Some(author_id) = author_id {
+                query = query.filter(posts::author_id.eq(author_id));
+            }
+
+            let res = query
+                .order((posts::deleted_at.desc(), posts::id.desc()))
+                .select((PostDb::as_select(), users::email.nullable()))
+                .load::<(PostDb, Option<String>)>(conn)?;
+
+            let ids: Vec<Uuid> = res.iter().map(|(post_db, _)| post_db.id).collect();
+            let tags = load_tags(conn, &ids)?;
+
+            Ok::<_, diesel::result::Error>((res, tags))
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    let (res, mut tags) = res;
+    Ok(res
+        .into_iter()
+        .map(|(post_db, author_email)| {
+            let post_tags = tags.remove(&post_db.id).unwrap_or_default();
+            adapt_post_db_to_post(post_db, author_email, post_tags)
+        })
+        .collect())
+}
+
+pub async fn restore(
+    pool: &deadpool_diesel::postgres::Pool,
+    id: Uuid,
+    author_id: Option<Uuid>,
+) -> Result<PostModel, InfraError> {
+    debug!("->> {:<12} - restore", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    // Take the post out of the trash
+    let (post_db, author_email, tags) = conn
+        .interact(move |conn| {
+            conn.transaction(|conn| {
+                diesel::update(
+                    posts::table
+                        .filter(posts::id.eq(id))
+                        .filter(posts::deleted_at.is_not_null())
+                        .filter(written_by(author_id)),
+                )
+                .set(posts::deleted_at.eq(None::<DateTime<Utc>>))
+                .returning(posts::id)
+                .get_result::<Uuid>(conn)?;
+
+                get_with_author(conn, id)
+            })
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(adapt_post_db_to_post(post_db, author_email, tags))
+}
+
+pub async fn schedule(
+    pool: &deadpool_diesel::postgres::Pool,
+    id: Uuid,
+    publish_at: Option<DateTime<Utc>>,
+    author_id: Option<Uuid>,
+) -> Result<PostModel, InfraError> {
+    debug!("->> {:<12} - schedule", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    // Only drafts can be scheduled; clearing `publish_at` cancels the schedule
+    let (post_db, author_email, tags) = conn
+        .interact(move |conn| {
+            conn.transaction(|conn| {
+                diesel::update(
+                    posts::table
+                        .filter(posts::id.eq(id))
+                        .filter(posts::deleted_at.is_null())
+                        .filter(posts::published.eq(false))
+                        .filter(written_by(author_id)),
+                )
+                .set(posts::publish_at.eq(publish_at))
+                .returning(posts::id)
+                .get_result::<Uuid>(conn)?;
+
+                get_with_author(conn, id)
+            })
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(adapt_post_db_to_post(post_db, author_email, tags))
+}
+
+pub async fn publish_due(
+    pool: &deadpool_diesel::postgres::Pool,
+    now: DateTime<Utc>,
+) -> Result<usize, InfraError> {
+    debug!("->> {:<12} - publish_due", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    // Rows claimed by another instance are skipped rather than waited on, so several
+    // schedulers can run side by side without publishing the same post twice
+    let res = conn
+        .interact(move |conn| {
+            let mut published = 0;
+            loop {
+                let batch = conn.transaction(|conn| {
+                    let due = posts::table
+                        .filter(posts::published.eq(false))
+                        .filter(posts::deleted_at.is_null())
+                        .filter(posts::publish_at.le(now))
+                        .select((posts::id, posts::publish_at))
+                        .limit(PUBLISH_BATCH_SIZE)
+                        .for_update()
+                        .skip_locked()
+                        .load::<(Uuid, Option<DateTime<Utc>>)>(conn)?;
+
+                    for (id, publish_at) in &due {
+                        diesel::update(posts::table.filter(posts::id.eq(id)))
+                            .set((
+                                posts::published.eq(true),
+                                posts::published_at.eq(publish_at),
+                                posts::publish_at.eq(None::<DateTime<Utc>>),
+                            ))
+                            .execute(conn)?;
+                    }
+
+                    Ok::<_, diesel::result::Error>(due.len())
+                })?;
+                published += batch;
+
+                if (batch as i64) < PUBLISH_BATCH_SIZE {
+                    return Ok::<_, diesel::result::Error>(published);
+                }
+            }
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(res)
+}
+
+pub async fn purge_trashed(
+    pool: &deadpool_diesel::postgres::Pool,
+    deleted_before: DateTime<Utc>,
+) -> Result<usize, InfraError> {
+    debug!("->> {:<12} - purge_trashed", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let res = conn
+        .interact(move |conn| {
+            delete_in_batches(conn, |conn, limit| {
+                let batch = posts::table
+                    .filter(posts::deleted_at.lt(deleted_before))
+                    .select(posts::id)
+                    .limit(limit)
+                    .load::<Uuid>(conn)?;
+
+                diesel::delete(posts::table.filter(posts::id.eq_any(batch))).execute(conn)
+            })
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(res)
+}
+
+// Find the first of `base`, `base-2`, `base-3`, ... that no post uses, now or in its history
+fn unique_slug(conn: &mut PgConnection, base: &str) -> QueryResult<String> {
+    let pattern = format!("{}-%", base);
+
+    let mut taken: HashSet<String> = posts::table
+        .filter(posts::slug.eq(base).or(posts::slug.like(&pattern)))
+        .select(posts::slug)
+        .load::<String>(conn)?
+        .into_iter()
+        .collect();
+    taken.extend(
+        post_slug_history::table
+            .filter(
+                post_slug_history::slug
+                    .eq(base)
+                    .or(post_slug_history::slug.like(&pattern)),
+            )
+            .select(post_slug_history::slug)
+            .load::<String>(conn)?,
+    );
+
+    if !taken.contains(base) {
+        return Ok(base.to_string());
+    }
+
+    Ok((2..)
+        .map(|n| format!("{}-{}", base, n))
+        .find(|candidate| !taken.contains(candidate))
+        .unwrap_or_default())
+}
+
+// Build the posts query with the filtering conditions that don't depend on paging
+fn filtered_posts(filter: &PostsFilter) -> PostsWithAuthorQuery {
+    let mut query = posts::table
+        .left_join(users::table)
+        .filter(posts::deleted_at.is_null())
+        .into_boxed();
+
+    if let Some(published) = filter.published {
+        query = query.filter(posts::published.eq(published));
+    }
+
+    if let Some(title_contains) = &filter.title_contains {
+        query = query.filter(posts::title.ilike(contains_pattern(title_contains)));
+    }
+
+    if let Some(author_id) = filter.author_id {
+        query = query.filter(posts::author_id.eq(author_id));
+    }
+
+    // Date ranges include their start and exclude their end
+    if let Some(created_after) = filter.created_after {
+        query = query.filter(posts::created_at.ge(created_after));
+    }
+
+    if let Some(created_before) = filter.created_before {
+        query = query.filter(posts::created_at.lt(created_before));
+    }
+
+    if let Some(updated_after) = filter.updated_after {
+        query = query.filter(posts::updated_at.ge(updated_after));
+    }
+
+    if let Some(updated_before) = filter.updated_before {
+        query = query.filter(posts::updated_at.lt(updated_before));
+    }
+
+    if let Some(published_after) = filter.published_after {
+        query = query.filter(posts::published_at.ge(published_after));
+    }
+
+    if let Some(published_before) = filter.published_before {
+        query = query.filter(posts::published_at.lt(published_before));
+    }
+
+    if let Some(tsquery) = filter.tsquery() {
+        query = query.filter(posts::search_vector.matches(parse_tsquery(&tsquery)));
+    }
+
+    // `tags=a,b` holds comma-separated tag names, normalized the same way as when they're set
+    if let Some(tags) = &filter.tags {
+        let mut names: Vec<String> = tags
+            .split(',')
+            .map(slugify)
+            .filter(|name| !name.is_empty())
+            .collect();
+        names.sort();
+        names.dedup();
+
+        if !names.is_empty() {
+            let tagged = post_tags::table
+                .inner_join(tags::table)
+                .filter(tags::name.eq_any(names.clone()))
+                .select(post_tags::post_id);
+
+            query = match filter.tags_match.unwrap_or_default() {
+                TagsMatch::Any => query.filter(posts::id.eq_any(tagged)),
+                // A post has all the tags when every one of them has a row of its own
+                TagsMatch::All => query.filter(
+                    posts::id.eq_any(
+                        tagged
+                            .group_by(post_tags::post_id)
+                            .having(count_star().eq(names.len() as i64)),
+                    ),
+                ),
+            };
+        }
+    }
+
+    query
+}
+
+fn parse_tsquery(tsquery: &str) -> to_tsquery_with_search_config<TsConfigurationByName, String> {
+    to_tsquery_with_search_config(SEARCH_CONFIG, tsquery.to_owned())
+}
+
+// Translate the user's search into `to_tsquery` syntax: "quoted words" must appear as a phrase,
+// a trailing `*` turns a word into a prefix match and all the parts have to match together.
+// Anything that isn't a letter or a digit is dropped so the input can never break the syntax.
+fn build_tsquery(q: &str) -> Option<String> {
+    let lexeme = |word: &str| {
+        let prefix = word.ends_with('*');
+        let word: String = word.chars().filter(|c| c.is_alphanumeric()).collect();
+
+        match (word.is_empty(), prefix) {
+            (true, _) => None,
+            (false, true) => Some(format!("{}:*", word)),
+            (false, false) => Some(word),
+        }
+    };
+
+    let parts: Vec<String> = q
+        .split('"')
+        .enumerate()
+        .flat_map(|(i, chunk)| {
+            let words = chunk.split_whitespace().filter_map(lexeme);
+
+            // Every odd chunk sits between a pair of quotes
+            if i % 2 == 1 {
+                let phrase: Vec<String> = words.collect();
+                if phrase.is_empty() {
+                    vec![]
+                } else {
+                    vec![format!("({})", phrase.join(" <-> "))]
+                }
+            } else {
+                words.collect()
+            }
+        })
+        .collect();
+
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join(" & "))
+    }
+}
+
+// Posts by the given author, or any post at all without one. Writes on behalf of an author filter
+// by it in the same statement, so a post can't change hands between checking and changing it.
+fn written_by(author_id: Option<Uuid>) -> PostPredicate {
+    match author_id {
+        Some(author_id) => Box::new(posts::author_id.eq(author_id)),
+        None => Box::new(AsExpression::<Bool>::as_expression(true).nullable()),
+    }
+}
+
+// Select a single post joined with the email of its author, if any, along with its tags
+fn get_with_author(conn: &mut PgConnection, id: Uuid) -> QueryResult<PostRow> {
+    posts::table
+        .left_join(users::table)
+        .filter(posts::id.eq(id))
+        .select((PostDb::as_select(), users::email.nullable()))
+        .get_result(conn)
+        .and_then(|row| with_tags(conn, row))
+}
+
+fn with_tags(
+    conn: &mut PgConnection,
+    (post_db, author_email): (PostDb, Option<String>),
+) -> QueryResult<PostRow> {
+    let tags = load_tags(conn, &[post_db.id])?
+        .remove(&post_db.id)
+        .unwrap_or_default();
+
+    Ok((post_db, author_email, tags))
+}
+
+// Load the tag names of many posts at once, so that a page costs one query instead of one per post
+fn load_tags(
+    conn: &mut PgConnection,
+    post_ids: &[Uuid],
+) -> QueryResult<HashMap<Uuid, Vec<String>>> {
+    let rows = post_tags::table
+        .inner_join(tags::table)
+        .filter(post_tags::post_id.eq_any(post_ids))
+        .order(tags::name.asc())
+        .select((post_tags::post_id, tags::name))
+        .load::<(Uuid, String)>(conn)?;
+
+    let mut res: HashMap<Uuid, Vec<String>> = HashMap::new();
+    for (post_id, name) in rows {
+        res.entry(post_id).or_default().push(name);
+    }
+
+    Ok(res)
+}
+
+// Make the post carry exactly the given tags, creating the ones that don't exist yet
+fn set_tags(conn: &mut PgConnection, post_id: Uuid, names: &[String]) -> QueryResult<()> {
+    diesel::insert_into(tags::table)
+        .values(
+            names
+                .iter()
+                .map(|name| tags::name.eq(name))
+                .collect::<Vec<_>>(),
+        )
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+
+    let tag_ids = tags::table
+        .filter(tags::name.eq_any(names))
+        .select(tags::id)
+        .load::<Uuid>(conn)?;
+
+    diesel::delete(
+        post_tags::table
+            .filter(post_tags::post_id.eq(post_id))
+            .filter(post_tags::tag_id.ne_all(&tag_ids)),
+    )
+    .execute(conn)?;
+
+    diesel::insert_into(post_tags::table)
+        .values(
+            tag_ids
+                .iter()
+                .map(|tag_id| (post_tags::post_id.eq(post_id), post_tags::tag_id.eq(tag_id)))
+                .collect::<Vec<_>>(),
+        )
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+
+    Ok(())
+}
+
+fn adapt_post_db_to_post(
+    post_db: PostDb,
+    author_email: Option<String>,
+    tags: Vec<String>,
+) -> PostModel {
     PostModel {
         id: post_db.id,
         title: post_db.title,
         body: post_db.body,
         published: post_db.published,
+        author_id: post_db.author_id,
+        author_email,
+        created_at: post_db.created_at,
+        updated_at: post_db.updated_at,
+        published_at: post_db.published_at,
+        deleted_at: post_db.deleted_at,
+        publish_at: post_db.publish_at,
+        slug: post_db.slug,
+        tags,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use deadpool_diesel::postgres::{Manager, Pool};
+    use deadpool_diesel::Runtime;
+
+    // These run against a real database with all migrations applied, e.g.
+    // `TEST_DATABASE_URL=postgres://localhost/blog_test cargo test -- --ignored`
+    fn test_pool() -> Pool {
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set");
+        Pool::builder(Manager::new(url, Runtime::Tokio1))
+            .build()
+            .unwrap()
+    }
+
+    async fn create_author(pool: &Pool) -> Uuid {
+        let email = format!("{}@example.com", Uuid::new_v4());
+        let conn = pool.get().await.unwrap();
+        conn.interact(move |conn| {
+            diesel::insert_into(users::table)
+                .values(users::email.eq(email))
+                .returning(users::id)
+                .get_result(conn)
+        })
+        .await
+        .unwrap()
+        .unwrap()
+    }
+
+    async fn create_post(pool: &Pool, author_id: Uuid) -> PostModel {
+        let new_post = NewPostDb {
+            title: String::from("Title"),
+            body: String::from("Body"),
+            published: false,
+            author_id,
+            slug: String::from("title"),
+        };
+        insert(pool, new_post, Vec::new(), true).await.unwrap()
+    }
+
+    async fn delete_author(pool: &Pool, author_id: Uuid) {
+        let conn = pool.get().await.unwrap();
+        conn.interact(move |conn| {
+            diesel::delete(posts::table.filter(posts::author_id.eq(author_id))).execute(conn)?;
+            diesel::delete(users::table.filter(users::id.eq(author_id))).execute(conn)
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    }
+
+    fn update_request() -> UpdatePostRequest {
+        UpdatePostRequest {
+            title: None,
+            body: None,
+            published: None,
+            slug: None,
+            tags: None,
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a database in TEST_DATABASE_URL"]
+    async fn updates_only_the_tags() {
+        let pool = test_pool();
+        let author_id = create_author(&pool).await;
+        let post = create_post(&pool, author_id).await;
+
+        let request = UpdatePostRequest {
+            tags: Some(vec![String::from("rust")]),
+            ..update_request()
+        };
+        let updated = update(&pool, post.id, request, author_id, Some(author_id)).await;
+
+        delete_author(&pool, author_id).await;
+        let updated = updated.unwrap();
+        assert_eq!(updated.tags, vec![String::from("rust")]);
+        assert_eq!(updated.title, post.title);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a database in TEST_DATABASE_URL"]
+    async fn keeps_the_post_as_is_without_changes() {
+        let pool = test_pool();
+        let author_id = create_author(&pool).await;
+        let post = create_post(&pool, author_id).await;
+
+        let unchanged = UpdatePostRequest {
+            title: Some(post.title.clone()),
+            body: Some(post.body.clone()),
+            slug: Some(post.slug.clone()),
+            ..update_request()
+        };
+        let unchanged = update(&pool, post.id, unchanged, author_id, Some(author_id)).await;
+        let empty = update(&pool, post.id, update_request(), author_id, Some(author_id)).await;
+        let revisions = post_revision_repository::get_all(&pool, post.id).await;
+
+        delete_author(&pool, author_id).await;
+        assert_eq!(unchanged.unwrap(), post);
+        assert_eq!(empty.unwrap(), post);
+        assert_eq!(revisions.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a database in TEST_DATABASE_URL"]
+    async fn records_a_revision_only_for_new_content() {
+        let pool = test_pool();
+        let author_id = create_author(&pool).await;
+        let post = create_post(&pool, author_id).await;
+
+        let publish = UpdatePostRequest {
+            published: Some(true),
+            ..update_request()
+        };
+        let published = update(&pool, post.id, publish, author_id, Some(author_id)).await;
+        let edit = UpdatePostRequest {
+            body: Some(String::from("New body")),
+            ..update_request()
+        };
+        let edited = update(&pool, post.id, edit, author_id, Some(author_id)).await;
+        let revisions = post_revision_repository::get_all(&pool, post.id).await;
+
+        delete_author(&pool, author_id).await;
+        assert!(published.unwrap().published);
+        assert_eq!(edited.unwrap().body, "New body");
+        let revisions = revisions.unwrap();
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(revisions[0].body, "New body");
     }
 }
@@ -0,0 +1,137 @@
+use crate::domain::models::post_revision::PostRevisionModel;
+use crate::infra::db::schema::{post_revisions, posts};
+use crate::infra::errors::{adapt_infra_error, InfraError};
+use chrono::{DateTime, Utc};
+use diesel::{
+    dsl::max, ExpressionMethods, Insertable, PgConnection, QueryDsl, QueryResult, Queryable,
+    RunQueryDsl, Selectable, SelectableHelper,
+};
+use serde::{Deserialize, Serialize};
+use tracing::log::debug;
+use uuid::Uuid;
+
+#[derive(Serialize, Queryable, Selectable)]
+#[diesel(table_name = post_revisions)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PostRevisionDb {
+    pub id: Uuid,
+    pub post_id: Uuid,
+    pub revision: i32,
+    pub title: String,
+    pub body: String,
+    pub editor_id: Option<Uuid>,
+    pub restored_from: Option<i32>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Insertable)]
+#[diesel(table_name = post_revisions)]
+struct NewPostRevisionDb {
+    post_id: Uuid,
+    revision: i32,
+    title: String,
+    body: String,
+    editor_id: Option<Uuid>,
+    restored_from: Option<i32>,
+}
+
+// Snapshot the current content of a post as its next revision. Meant to be called inside the
+// transaction that changed the post, after the post row has been written or locked.
+pub fn record(
+    conn: &mut PgConnection,
+    post_id: Uuid,
+    editor_id: Option<Uuid>,
+    restored_from: Option<i32>,
+) -> QueryResult<i32> {
+    let (title, body) = posts::table
+        .filter(posts::id.eq(post_id))
+        .select((posts::title, posts::body))
+        .get_result::<(String, String)>(conn)?;
+
+    let head = post_revisions::table
+        .filter(post_revisions::post_id.eq(post_id))
+        .select(max(post_revisions::revision))
+        .get_result::<Option<i32>>(conn)?;
+
+    let new_revision = NewPostRevisionDb {
+        post_id,
+        revision: head.unwrap_or(0) + 1,
+        title,
+        body,
+        editor_id,
+        restored_from,
+    };
+
+    diesel::insert_into(post_revisions::table)
+        .values(new_revision)
+        .returning(post_revisions::revision)
+        .get_result(conn)
+}
+
+pub fn find(conn: &mut PgConnection, post_id: Uuid, revision: i32) -> QueryResult<PostRevisionDb> {
+    post_revisions::table
+        .filter(post_revisions::post_id.eq(post_id))
+        .filter(post_revisions::revision.eq(revision))
+        .select(PostRevisionDb::as_select())
+        .get_result(conn)
+}
+
+pub async fn get_all(
+    pool: &deadpool_diesel::postgres::Pool,
+    post_id: Uuid,
+) -> Result<Vec<PostRevisionModel>, InfraError> {
+    debug!("->> {:<12} - get_all", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    // Newest revisions come first
+    let res = conn
+        .interact(move |conn| {
+            post_revisions::table
+                .filter(post_revisions::post_id.eq(post_id))
+                .order(post_revisions::revision.desc())
+                .select(PostRevisionDb::as_select())
+                .load::<PostRevisionDb>(conn)
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(res
+        .into_iter()
+        .map(adapt_post_revision_db_to_post_revision)
+        .collect())
+}
+
+pub async fn get(
+    pool: &deadpool_diesel::postgres::Pool,
+    post_id: Uuid,
+    revision: i32,
+) -> Result<PostRevisionModel, InfraError> {
+    debug!("->> {:<12} - get", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let res = conn
+        .interact(move |conn| find(conn, post_id, revision))
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(adapt_post_revision_db_to_post_revision(res))
+}
+
+fn adapt_post_revision_db_to_post_revision(revision_db: PostRevisionDb) -> PostRevisionModel {
+    PostRevisionModel {
+        id: revision_db.id,
+        post_id: revision_db.post_id,
+        revision: revision_db.revision,
+        title: revision_db.title,
+        body: revision_db.body,
+        editor_id: revision_db.editor_id,
+        restored_from: revision_db.restored_from,
+        created_at: revision_db.created_at,
+    }
+}
@@ -0,0 +1,41 @@
+use crate::domain::models::tag::TagModel;
+use crate::infra::db::schema::{post_tags, posts, tags};
+use crate::infra::errors::{adapt_infra_error, InfraError};
+use diesel::{
+    dsl::count, BoolExpressionMethods, ExpressionMethods, JoinOnDsl, NullableExpressionMethods,
+    QueryDsl, RunQueryDsl,
+};
+use tracing::log::debug;
+
+pub async fn get_all_with_counts(
+    pool: &deadpool_diesel::postgres::Pool,
+) -> Result<Vec<TagModel>, InfraError> {
+    debug!("->> {:<12} - get_all_with_counts", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    // Count the posts carrying each tag, leaving trashed posts out; unused tags count zero
+    let res = conn
+        .interact(|conn| {
+            tags::table
+                .left_join(post_tags::table)
+                .left_join(
+                    posts::table.on(posts::id
+                        .eq(post_tags::post_id)
+                        .and(posts::deleted_at.is_null())),
+                )
+                .group_by(tags::name)
+                .select((tags::name, count(posts::id.nullable())))
+                .order((count(posts::id.nullable()).desc(), tags::name.asc()))
+                .load::<(String, i64)>(conn)
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(res
+        .into_iter()
+        .map(|(name, post_count)| TagModel { name, post_count })
+        .collect())
+}
@@ -0,0 +1,251 @@
+use crate::domain::models::user_identity::UserIdentityModel;
+use crate::infra::db::schema::{user_identities, users};
+use crate::infra::errors::{adapt_infra_error, InfraError};
+use crate::infra::repositories::user_repository::NewUserDb;
+use chrono::{DateTime, Utc};
+use diesel::{
+    dsl::count_star, Connection, ExpressionMethods, Insertable, QueryDsl, Queryable, RunQueryDsl,
+    Selectable, SelectableHelper,
+};
+use serde::{Deserialize, Serialize};
+use tracing::log::debug;
+use uuid::Uuid;
+
+#[derive(Serialize, Queryable, Selectable)]
+#[diesel(table_name = user_identities)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct UserIdentityDb {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub provider: String,
+    pub subject: String,
+    pub email: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Insertable)]
+#[diesel(table_name = user_identities)]
+pub struct NewUserIdentityDb {
+    pub user_id: Uuid,
+    pub provider: String,
+    pub subject: String,
+    pub email: String,
+}
+
+pub async fn insert(
+    pool: &deadpool_diesel::postgres::Pool,
+    new_identity: NewUserIdentityDb,
+) -> Result<UserIdentityModel, InfraError> {
+    debug!("->> {:<12} - insert", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let res = conn
+        .interact(|conn| {
+            diesel::insert_into(user_identities::table)
+                .values(new_identity)
+                .returning(UserIdentityDb::as_returning())
+                .get_result(conn)
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(adapt_user_identity_db_to_user_identity(res))
+}
+
+// Create a user together with the identity they logged in with, returning the user's ID
+pub async fn insert_with_user(
+    pool: &deadpool_diesel::postgres::Pool,
+    provider: String,
+    subject: String,
+    email: String,
+) -> Result<Uuid, InfraError> {
+    debug!("->> {:<12} - insert_with_user", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let res = conn
+        .interact(|conn| {
+            conn.transaction(|conn| {
+                let user_id = diesel::insert_into(users::table)
+                    .values(NewUserDb {
+                        email: email.clone(),
+                    })
+                    .returning(users::id)
+                    .get_result::<Uuid>(conn)?;
+
+                let new_identity = NewUserIdentityDb {
+                    user_id,
+                    provider,
+                    subject,
+                    email,
+                };
+
+                diesel::insert_into(user_identities::table)
+                    .values(new_identity)
+                    .execute(conn)?;
+
+                Ok::<_, diesel::result::Error>(user_id)
+            })
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(res)
+}
+
+pub async fn get_by_subject(
+    pool: &deadpool_diesel::postgres::Pool,
+    provider: String,
+    subject: String,
+) -> Result<UserIdentityModel, InfraError> {
+    debug!("->> {:<12} - get_by_subject", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let res = conn
+        .interact(|conn| {
+            user_identities::table
+                .filter(user_identities::provider.eq(provider))
+                .filter(user_identities::subject.eq(subject))
+                .select(UserIdentityDb::as_select())
+                .get_result(conn)
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(adapt_user_identity_db_to_user_identity(res))
+}
+
+pub async fn get_all_by_user(
+    pool: &deadpool_diesel::postgres::Pool,
+    user_id: Uuid,
+) -> Result<Vec<UserIdentityModel>, InfraError> {
+    debug!("->> {:<12} - get_all_by_user", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let res = conn
+        .interact(move |conn| {
+            user_identities::table
+                .filter(user_identities::user_id.eq(user_id))
+                .order(user_identities::created_at.asc())
+                .select(UserIdentityDb::as_select())
+                .load::<UserIdentityDb>(conn)
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(res
+        .into_iter()
+        .map(adapt_user_identity_db_to_user_identity)
+        .collect())
+}
+
+// Keep the address a provider reports for an identity up to date
+pub async fn update_email(
+    pool: &deadpool_diesel::postgres::Pool,
+    id: Uuid,
+    email: String,
+) -> Result<(), InfraError> {
+    debug!("->> {:<12} - update_email", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    conn.interact(move |conn| {
+        diesel::update(user_identities::table.filter(user_identities::id.eq(id)))
+            .set(user_identities::email.eq(email))
+            .execute(conn)
+    })
+    .await
+    .map_err(adapt_infra_error)?
+    .map_err(adapt_infra_error)?;
+
+    Ok(())
+}
+
+// Unlink one of the identities of a user, unless it's the only one they have left to log in
+// with. Returns whether it was removed.
+pub async fn delete_unless_last(
+    pool: &deadpool_diesel::postgres::Pool,
+    id: Uuid,
+    user_id: Uuid,
+) -> Result<bool, InfraError> {
+    debug!("->> {:<12} - delete_unless_last", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let res = conn
+        .interact(move |conn| {
+            conn.transaction(|conn| {
+                // Lock all identities of the user, so two unlinks can't each leave the other one
+                let identity_ids = user_identities::table
+                    .filter(user_identities::user_id.eq(user_id))
+                    .select(user_identities::id)
+                    .for_update()
+                    .load::<Uuid>(conn)?;
+
+                if !identity_ids.contains(&id) {
+                    return Err(diesel::result::Error::NotFound);
+                }
+
+                if identity_ids.len() < 2 {
+                    return Ok(false);
+                }
+
+                diesel::delete(user_identities::table.filter(user_identities::id.eq(id)))
+                    .execute(conn)?;
+
+                Ok(true)
+            })
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(res)
+}
+
+pub async fn count_by_user(
+    pool: &deadpool_diesel::postgres::Pool,
+    user_id: Uuid,
+) -> Result<i64, InfraError> {
+    debug!("->> {:<12} - count_by_user", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let res = conn
+        .interact(move |conn| {
+            user_identities::table
+                .filter(user_identities::user_id.eq(user_id))
+                .select(count_star())
+                .get_result(conn)
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(res)
+}
+
+fn adapt_user_identity_db_to_user_identity(identity_db: UserIdentityDb) -> UserIdentityModel {
+    UserIdentityModel {
+        id: identity_db.id,
+        user_id: identity_db.user_id,
+        provider: identity_db.provider,
+        subject: identity_db.subject,
+        email: identity_db.email,
+        created_at: identity_db.created_at,
+    }
+}
@@ -1,9 +1,13 @@
-use crate::domain::models::user::UserModel;
-use crate::infra::db::schema::users;
+use crate::domain::models::role::Role;
+use crate::domain::models::user::{UserModel, UserOverviewModel, UsersPage};
+use crate::infra::db::query::contains_pattern;
+use crate::infra::db::schema::{posts, user_sessions, users};
 use crate::infra::errors::{adapt_infra_error, InfraError};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
 use diesel::{
-    ExpressionMethods, Insertable, OptionalExtension, QueryDsl, Queryable, RunQueryDsl, Selectable,
-    SelectableHelper,
+    ExpressionMethods, Insertable, PgTextExpressionMethods, QueryDsl, Queryable, RunQueryDsl,
+    Selectable, SelectableHelper,
 };
 use serde::{Deserialize, Serialize};
 use tracing::log::debug;
@@ -15,6 +19,9 @@ use uuid::Uuid;
 pub struct UserDb {
     pub id: Uuid,
     pub email: String,
+    pub role: String,
+    pub created_at: DateTime<Utc>,
+    pub suspended_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Deserialize, Insertable)]
@@ -23,49 +30,64 @@ pub struct NewUserDb {
     pub email: String,
 }
 
-pub async fn insert_if_not_exists(
+const DEFAULT_PAGE_SIZE: i64 = 20;
+const MAX_PAGE_SIZE: i64 = 100;
+
+// Users come ordered by email address; `q` matches part of it
+#[derive(Deserialize)]
+pub struct UsersFilter {
+    q: Option<String>,
+    role: Option<Role>,
+    suspended: Option<bool>,
+    limit: Option<i64>,
+    cursor: Option<UsersCursor>,
+}
+
+// Opaque keyset cursor: the email address of the last user of a page, base64-encoded
+#[derive(Deserialize)]
+#[serde(try_from = "String")]
+pub struct UsersCursor(String);
+
+impl UsersCursor {
+    fn encode(user_db: &UserDb) -> String {
+        URL_SAFE_NO_PAD.encode(&user_db.email)
+    }
+}
+
+impl TryFrom<String> for UsersCursor {
+    type Error = &'static str;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(value)
+            .map_err(|_| "cursor is not valid base64")?;
+        let email = String::from_utf8(bytes).map_err(|_| "cursor is malformed")?;
+
+        Ok(Self(email))
+    }
+}
+
+pub async fn get_by_email(
     pool: &deadpool_diesel::postgres::Pool,
     email: String,
-) -> Result<Uuid, InfraError> {
-    debug!("->> {:<12} - insert_if_not_exists", "INFRASTRUCTURE");
+) -> Result<UserModel, InfraError> {
+    debug!("->> {:<12} - get_by_email", "INFRASTRUCTURE");
 
     // Get a database connection from the pool and handle any potential errors
     let conn = pool.get().await.map_err(adapt_infra_error)?;
 
-    let email_for_check = email.clone();
-    // Check if user exists
-    let existing_user = conn
+    let res = conn
         .interact(|conn| {
             users::table
-                .filter(users::email.eq(email_for_check))
-                .first::<UserDb>(conn)
-                .optional()
+                .filter(users::email.eq(email))
+                .select(UserDb::as_select())
+                .get_result(conn)
         })
         .await
         .map_err(adapt_infra_error)?
         .map_err(adapt_infra_error)?;
 
-    // Create new user if necessary
-    let user_id = match existing_user {
-        Some(user) => user.id, // User already exists, use their ID
-        None => {
-            let id = conn
-                .interact(|conn| {
-                    let new_user = NewUserDb { email }; // Create a new user struct
-                    diesel::insert_into(users::table)
-                        .values(&new_user)
-                        .returning(users::id)
-                        .get_result(conn)
-                })
-                .await
-                .map_err(adapt_infra_error)?
-                .map_err(adapt_infra_error)?;
-
-            id
-        }
-    };
-
-    Ok(user_id)
+    Ok(adapt_user_db_to_user_model(res))
 }
 
 pub async fn get(
@@ -91,9 +113,165 @@ pub async fn get(
     Ok(adapt_user_db_to_user_model(res))
 }
 
+pub async fn get_all(
+    pool: &deadpool_diesel::postgres::Pool,
+    filter: UsersFilter,
+) -> Result<UsersPage, InfraError> {
+    debug!("->> {:<12} - get_all", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let limit = filter
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    let mut res = conn
+        .interact(move |conn| {
+            let mut query = users::table.into_boxed();
+
+            if let Some(q) = &filter.q {
+                query = query.filter(users::email.ilike(contains_pattern(q)));
+            }
+
+            if let Some(role) = filter.role {
+                query = query.filter(users::role.eq(role.as_str()));
+            }
+
+            match filter.suspended {
+                Some(true) => query = query.filter(users::suspended_at.is_not_null()),
+                Some(false) => query = query.filter(users::suspended_at.is_null()),
+                None => {}
+            }
+
+            if let Some(UsersCursor(email)) = filter.cursor {
+                query = query.filter(users::email.gt(email));
+            }
+
+            // Fetch one extra row to find out whether there is a next page
+            query
+                .order(users::email.asc())
+                .limit(limit + 1)
+                .select(UserDb::as_select())
+                .load::<UserDb>(conn)
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    let next_cursor = if res.len() as i64 > limit {
+        res.truncate(limit as usize);
+        res.last().map(UsersCursor::encode)
+    } else {
+        None
+    };
+
+    Ok(UsersPage {
+        users: res.into_iter().map(adapt_user_db_to_user_model).collect(),
+        next_cursor,
+    })
+}
+
+// A user together with how many live sessions and posts outside the trash they have
+pub async fn get_overview(
+    pool: &deadpool_diesel::postgres::Pool,
+    id: Uuid,
+    now: i64,
+) -> Result<UserOverviewModel, InfraError> {
+    debug!("->> {:<12} - get_overview", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let (user_db, session_count, post_count) = conn
+        .interact(move |conn| {
+            let user_db = users::table
+                .filter(users::id.eq(id))
+                .select(UserDb::as_select())
+                .get_result(conn)?;
+
+            let session_count = user_sessions::table
+                .filter(user_sessions::user_id.eq(id))
+                .filter(user_sessions::expires_at.gt(now))
+                .count()
+                .get_result::<i64>(conn)?;
+
+            let post_count = posts::table
+                .filter(posts::author_id.eq(id))
+                .filter(posts::deleted_at.is_null())
+                .count()
+                .get_result::<i64>(conn)?;
+
+            Ok::<_, diesel::result::Error>((user_db, session_count, post_count))
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(UserOverviewModel {
+        user: adapt_user_db_to_user_model(user_db),
+        session_count,
+        post_count,
+    })
+}
+
+// Suspend a user as of `suspended_at`, or let them back in with `None`
+pub async fn set_suspended(
+    pool: &deadpool_diesel::postgres::Pool,
+    id: Uuid,
+    suspended_at: Option<DateTime<Utc>>,
+) -> Result<UserModel, InfraError> {
+    debug!("->> {:<12} - set_suspended", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let res = conn
+        .interact(move |conn| {
+            diesel::update(users::table.filter(users::id.eq(id)))
+                .set(users::suspended_at.eq(suspended_at))
+                .returning(UserDb::as_returning())
+                .get_result(conn)
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(adapt_user_db_to_user_model(res))
+}
+
+pub async fn set_role(
+    pool: &deadpool_diesel::postgres::Pool,
+    id: Uuid,
+    role: Role,
+) -> Result<UserModel, InfraError> {
+    debug!("->> {:<12} - set_role", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let res = conn
+        .interact(move |conn| {
+            diesel::update(users::table.filter(users::id.eq(id)))
+                .set(users::role.eq(role.as_str()))
+                .returning(UserDb::as_returning())
+                .get_result(conn)
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(adapt_user_db_to_user_model(res))
+}
+
 fn adapt_user_db_to_user_model(user_db: UserDb) -> UserModel {
     UserModel {
         id: user_db.id,
         email: user_db.email,
+        // The database only allows known roles, anything else grants nothing
+        role: Role::parse(&user_db.role).unwrap_or(Role::Reader),
+        created_at: user_db.created_at,
+        suspended_at: user_db.suspended_at,
     }
 }
@@ -1,4 +1,5 @@
 use crate::domain::models::user_session::UserSessionModel;
+use crate::infra::db::query::delete_in_batches;
 use crate::infra::db::schema::user_sessions;
 use crate::infra::errors::{adapt_infra_error, InfraError};
 use diesel::{
@@ -15,9 +16,16 @@ pub struct UserSessionDb {
     pub id: Uuid,
     pub user_id: Uuid,
     pub session_token_p1: String,
-    pub session_token_p2: String,
+    pub session_token_p2_digest: String,
     pub created_at: i64,
     pub expires_at: i64,
+    pub oauth_access_token: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub last_seen_at: i64,
+    pub previous_session_token_p2_digest: Option<String>,
+    pub rotated_at: Option<i64>,
+    pub oauth_provider: Option<String>,
 }
 
 #[derive(Deserialize, Insertable)]
@@ -25,9 +33,14 @@ pub struct UserSessionDb {
 pub struct NewUserSessionDb {
     pub user_id: Uuid,
     pub session_token_p1: String,
-    pub session_token_p2: String,
+    pub session_token_p2_digest: String,
     pub created_at: i64,
     pub expires_at: i64,
+    pub oauth_access_token: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub last_seen_at: i64,
+    pub oauth_provider: Option<String>,
 }
 
 pub async fn insert(
@@ -76,13 +89,244 @@ pub async fn get_by_first_part_token(
     Ok(adapt_user_session_to_user_session_model(res))
 }
 
+// Sessions of a user that haven't expired yet, most recently used first
+pub async fn get_all_by_user(
+    pool: &deadpool_diesel::postgres::Pool,
+    user_id: Uuid,
+    now: i64,
+) -> Result<Vec<UserSessionModel>, InfraError> {
+    debug!("->> {:<12} - get_all_by_user", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let res = conn
+        .interact(move |conn| {
+            user_sessions::table
+                .filter(user_sessions::user_id.eq(user_id))
+                .filter(user_sessions::expires_at.gt(now))
+                .order((user_sessions::last_seen_at.desc(), user_sessions::id.asc()))
+                .select(UserSessionDb::as_select())
+                .load::<UserSessionDb>(conn)
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(res
+        .into_iter()
+        .map(adapt_user_session_to_user_session_model)
+        .collect())
+}
+
+// Note that a session is in use, and from where
+pub async fn touch(
+    pool: &deadpool_diesel::postgres::Pool,
+    id: Uuid,
+    last_seen_at: i64,
+    ip_address: Option<String>,
+) -> Result<(), InfraError> {
+    debug!("->> {:<12} - touch", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    conn.interact(move |conn| {
+        diesel::update(user_sessions::table.filter(user_sessions::id.eq(id)))
+            .set((
+                user_sessions::last_seen_at.eq(last_seen_at),
+                user_sessions::ip_address.eq(ip_address),
+            ))
+            .execute(conn)
+    })
+    .await
+    .map_err(adapt_infra_error)?
+    .map_err(adapt_infra_error)?;
+
+    Ok(())
+}
+
+// Swap the digest of the secret half of a session token for a new one and move the expiry along.
+// Only happens if the digest is still `current_digest`, so of concurrent rotations just one wins;
+// returns whether this was the one.
+pub async fn rotate(
+    pool: &deadpool_diesel::postgres::Pool,
+    id: Uuid,
+    current_digest: String,
+    new_digest: String,
+    expires_at: i64,
+    now: i64,
+) -> Result<bool, InfraError> {
+    debug!("->> {:<12} - rotate", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let res = conn
+        .interact(move |conn| {
+            diesel::update(
+                user_sessions::table
+                    .filter(user_sessions::id.eq(id))
+                    .filter(user_sessions::session_token_p2_digest.eq(&current_digest)),
+            )
+            .set((
+                user_sessions::session_token_p2_digest.eq(new_digest),
+                user_sessions::previous_session_token_p2_digest.eq(&current_digest),
+                user_sessions::rotated_at.eq(now),
+                user_sessions::expires_at.eq(expires_at),
+            ))
+            .execute(conn)
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(res == 1)
+}
+
+// Remove one of the sessions of a user; sessions of anyone else are not found
+pub async fn delete_by_user(
+    pool: &deadpool_diesel::postgres::Pool,
+    id: Uuid,
+    user_id: Uuid,
+) -> Result<(), InfraError> {
+    debug!("->> {:<12} - delete_by_user", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    conn.interact(move |conn| {
+        diesel::delete(
+            user_sessions::table
+                .filter(user_sessions::id.eq(id))
+                .filter(user_sessions::user_id.eq(user_id)),
+        )
+        .returning(user_sessions::id)
+        .get_result::<Uuid>(conn)
+    })
+    .await
+    .map_err(adapt_infra_error)?
+    .map_err(adapt_infra_error)?;
+
+    Ok(())
+}
+
+// Remove every session of a user, returning how many were removed
+pub async fn delete_all_by_user(
+    pool: &deadpool_diesel::postgres::Pool,
+    user_id: Uuid,
+) -> Result<usize, InfraError> {
+    debug!("->> {:<12} - delete_all_by_user", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let res = conn
+        .interact(move |conn| {
+            diesel::delete(user_sessions::table.filter(user_sessions::user_id.eq(user_id)))
+                .execute(conn)
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(res)
+}
+
+// Remove every session of a user but the given one, returning how many were removed
+pub async fn delete_all_by_user_except(
+    pool: &deadpool_diesel::postgres::Pool,
+    user_id: Uuid,
+    keep_id: Uuid,
+) -> Result<usize, InfraError> {
+    debug!("->> {:<12} - delete_all_by_user_except", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let res = conn
+        .interact(move |conn| {
+            diesel::delete(
+                user_sessions::table
+                    .filter(user_sessions::user_id.eq(user_id))
+                    .filter(user_sessions::id.ne(keep_id)),
+            )
+            .execute(conn)
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(res)
+}
+
+// Remove a session, handing back what it was
+pub async fn delete(
+    pool: &deadpool_diesel::postgres::Pool,
+    id: Uuid,
+) -> Result<UserSessionModel, InfraError> {
+    debug!("->> {:<12} - delete", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let res = conn
+        .interact(move |conn| {
+            diesel::delete(user_sessions::table.filter(user_sessions::id.eq(id)))
+                .returning(UserSessionDb::as_returning())
+                .get_result(conn)
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(adapt_user_session_to_user_session_model(res))
+}
+
+// Remove sessions that expired before `now`
+pub async fn delete_expired(
+    pool: &deadpool_diesel::postgres::Pool,
+    now: i64,
+) -> Result<usize, InfraError> {
+    debug!("->> {:<12} - delete_expired", "INFRASTRUCTURE");
+
+    // Get a database connection from the pool and handle any potential errors
+    let conn = pool.get().await.map_err(adapt_infra_error)?;
+
+    let res = conn
+        .interact(move |conn| {
+            delete_in_batches(conn, |conn, limit| {
+                let batch = user_sessions::table
+                    .filter(user_sessions::expires_at.le(now))
+                    .select(user_sessions::id)
+                    .limit(limit)
+                    .load::<Uuid>(conn)?;
+
+                diesel::delete(user_sessions::table.filter(user_sessions::id.eq_any(batch)))
+                    .execute(conn)
+            })
+        })
+        .await
+        .map_err(adapt_infra_error)?
+        .map_err(adapt_infra_error)?;
+
+    Ok(res)
+}
+
 fn adapt_user_session_to_user_session_model(user_session: UserSessionDb) -> UserSessionModel {
     UserSessionModel {
         id: user_session.id,
         user_id: user_session.user_id,
         session_token_p1: user_session.session_token_p1,
-        session_token_p2: user_session.session_token_p2,
+        session_token_p2_digest: user_session.session_token_p2_digest,
         created_at: user_session.created_at,
         expires_at: user_session.expires_at,
+        oauth_access_token: user_session.oauth_access_token,
+        ip_address: user_session.ip_address,
+        user_agent: user_session.user_agent,
+        last_seen_at: user_session.last_seen_at,
+        previous_session_token_p2_digest: user_session.previous_session_token_p2_digest,
+        rotated_at: user_session.rotated_at,
+        oauth_provider: user_session.oauth_provider,
     }
 }
@@ -1,6 +1,7 @@
 use crate::config::config;
 use crate::routes::app_router;
 use deadpool_diesel::postgres::{Manager, Pool};
+use std::net::SocketAddr;
 use tracing::log::debug;
 
 mod config;
@@ -9,6 +10,7 @@ mod handlers;
 mod infra;
 mod middlewares;
 mod routes;
+mod tasks;
 
 #[derive(Clone)]
 pub struct AppState {
@@ -25,11 +27,21 @@ async fn main() {
         config.db_url().to_string(),
         deadpool_diesel::Runtime::Tokio1,
     );
-    let pool = Pool::builder(manager).build().unwrap();
+    // Without timeouts a request would wait for a connection for as long as the database is gone
+    let pool = Pool::builder(manager)
+        .wait_timeout(Some(config.db_pool_timeout()))
+        .create_timeout(Some(config.db_pool_timeout()))
+        .runtime(deadpool_diesel::Runtime::Tokio1)
+        .build()
+        .unwrap();
 
     let state = AppState { pool };
 
-    let app = app_router(state.clone()).with_state(state);
+    tokio::spawn(tasks::trash_purge::run(state.pool.clone(), config));
+    tokio::spawn(tasks::auth_janitor::run(state.pool.clone(), config));
+    tokio::spawn(tasks::scheduled_publish::run(state.pool.clone(), config));
+
+    let app = app_router(state.clone(), config).with_state(state);
 
     let host = config.server_host();
     let port = config.server_port();
@@ -39,5 +51,10 @@ async fn main() {
     let listener = tokio::net::TcpListener::bind(address).await.unwrap();
 
     debug!("LISTENING on {:?}\n", listener.local_addr().unwrap());
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
 }
@@ -1,84 +1,443 @@
+use crate::config::config;
 use crate::domain::models::auth::AuthError;
-use crate::handlers::auth::UserData;
-use crate::infra::repositories::{user_repository, user_sessions_repository};
+use crate::domain::models::role::Permission;
+use crate::domain::models::user::UserModel;
+use crate::domain::models::user_session::UserSessionModel;
+use crate::domain::problem::{RequestContext, REQUEST_CONTEXT};
+use crate::handlers::auth::create_token::TOKEN_PREFIX;
+use crate::handlers::auth::{
+    client_ip, digest_secret, login_url, session_cookie, Credential, UserData,
+};
+use crate::infra::repositories::{
+    personal_access_tokens_repository, user_repository, user_sessions_repository,
+};
 use crate::AppState;
 use axum::{
+    async_trait,
     body::Body,
-    extract::State,
-    http::Request,
+    extract::{ConnectInfo, FromRequestParts, OriginalUri, State},
+    http::{header, request::Parts, HeaderMap, HeaderName, HeaderValue, Request},
     middleware::Next,
-    response::{IntoResponse, Redirect},
+    response::{IntoResponse, Redirect, Response},
 };
 use axum_extra::TypedHeader;
 use chrono::Utc;
-use headers::Cookie;
+use headers::{authorization::Bearer, Authorization, Cookie};
+use std::marker::PhantomData;
+use std::net::SocketAddr;
+use tracing::log::{debug, warn};
+use uuid::Uuid;
+
+// How stale the last-seen time of a session may get before a request updates it, which keeps
+// every request from being a write
+const LAST_SEEN_RESOLUTION_SECS: i64 = 60;
+
+// For how long the secret a session had before its last rotation is still accepted
+const ROTATION_GRACE_SECS: i64 = 30;
+
+static X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+// Longest request id taken over from a proxy in front of us, anything else gets a fresh one
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+// Give every request an id, taken over from the proxy or made up here, and make it and the path
+// available to error responses. The id goes back in the response so both can be matched up.
+pub async fn request_context(request: Request<Body>, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(&X_REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN)
+        .map(String::from)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    let context = RequestContext {
+        request_id: request_id.clone(),
+        instance: request.uri().path().to_string(),
+    };
+
+    let mut response = REQUEST_CONTEXT.scope(context, next.run(request)).await;
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(X_REQUEST_ID.clone(), value);
+    }
+
+    response
+}
 
 pub async fn inject_user_data(
     State(state): State<AppState>,
     cookie: Option<TypedHeader<Cookie>>,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
     mut request: Request<Body>,
     next: Next,
 ) -> Result<impl IntoResponse, AuthError> {
-    if let Some(cookie) = cookie {
-        if let Some(session_token) = cookie.get("session_token") {
-            let session_token: Vec<&str> = session_token.split('_').collect();
-            let user_session = user_sessions_repository::get_by_first_part_token(
-                &state.pool,
-                session_token[0].chars().collect::<String>(),
-            )
+    let mut rotated_cookie = None;
+
+    let session_token = cookie
+        .as_ref()
+        .and_then(|cookie| cookie.get("session_token"));
+
+    // Scripts send a token rather than a cookie; should both be there, the token wins
+    let user_data = if let Some(TypedHeader(Authorization(bearer))) = &bearer {
+        token_user_data(&state, bearer.token()).await
+    } else if let Some(session_token) = session_token {
+        session_user_data(
+            &state,
+            session_token,
+            request.headers(),
+            peer,
+            &mut rotated_cookie,
+        )
+        .await
+    } else {
+        None
+    };
+
+    if let Some(user_data) = user_data {
+        request.extensions_mut().insert(Some(user_data));
+    }
+
+    let mut response = next.run(request).await;
+
+    if let Some(rotated_cookie) = rotated_cookie {
+        if let Ok(value) = HeaderValue::from_str(&rotated_cookie) {
+            response.headers_mut().append(header::SET_COOKIE, value);
+        }
+    }
+
+    Ok(response)
+}
+
+async fn session_user_data(
+    state: &AppState,
+    session_token: &str,
+    headers: &HeaderMap,
+    peer: SocketAddr,
+    rotated_cookie: &mut Option<String>,
+) -> Option<UserData> {
+    let now = Utc::now().timestamp();
+
+    let (user_session, secret_is_current) = find_session(state, session_token, now).await?;
+
+    let user = active_user(state, user_session.user_id).await?;
+
+    if now - user_session.last_seen_at >= LAST_SEEN_RESOLUTION_SECS {
+        let ip_address = client_ip(headers, peer).await;
+        if let Err(err) =
+            user_sessions_repository::touch(&state.pool, user_session.id, now, Some(ip_address))
+                .await
+        {
+            warn!("inject_user_data: failed to touch session: {}", err);
+        }
+    }
+
+    // A request made with the secret from before a rotation has already been handed the new one
+    if secret_is_current {
+        *rotated_cookie = slide_expiry(state, &user_session, now).await;
+    }
+
+    Some(UserData {
+        user_id: user_session.user_id,
+        user_email: user.email,
+        role: user.role,
+        permissions: user.role.permissions(),
+        credential: Credential::Session(user_session.id),
+    })
+}
+
+// Look up the live personal access token a bearer token is. Tokens are random enough that
+// finding them by their digest leaks nothing through timing.
+async fn token_user_data(state: &AppState, token: &str) -> Option<UserData> {
+    if !token.starts_with(TOKEN_PREFIX) {
+        return None;
+    }
+
+    let now = Utc::now();
+
+    let access_token =
+        personal_access_tokens_repository::get_by_digest(&state.pool, digest_secret(token), now)
             .await
-            .map_err(AuthError::InfraError);
-
-            if let Ok(query) = user_session {
-                if let Ok(session_token_p2_db) = query.session_token_p2.as_bytes().try_into() {
-                    if let Ok(session_token_p2_cookie) = session_token
-                        .get(1)
-                        .copied()
-                        .unwrap_or_default()
-                        .as_bytes()
-                        .try_into()
-                    {
-                        if constant_time_eq::constant_time_eq_n::<36>(
-                            session_token_p2_cookie,
-                            session_token_p2_db,
-                        ) {
-                            let user_id = query.user_id;
-                            let expires_at = query.expires_at;
-                            if expires_at > Utc::now().timestamp() {
-                                let user = user_repository::get(&state.pool, user_id)
-                                    .await
-                                    .map_err(AuthError::InfraError);
-                                if let Ok(query) = user {
-                                    let user_email = query.email;
-                                    request.extensions_mut().insert(Some(UserData {
-                                        user_id,
-                                        user_email,
-                                    }));
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+            .ok()?;
+
+    let user = active_user(state, access_token.user_id).await?;
+
+    let is_stale = access_token
+        .last_used_at
+        .is_none_or(|last_used_at| (now - last_used_at).num_seconds() >= LAST_SEEN_RESOLUTION_SECS);
+    if is_stale {
+        if let Err(err) =
+            personal_access_tokens_repository::touch(&state.pool, access_token.id, now).await
+        {
+            warn!("inject_user_data: failed to touch access token: {}", err);
         }
     }
 
-    Ok(next.run(request).await)
+    Some(UserData {
+        user_id: access_token.user_id,
+        user_email: user.email,
+        role: user.role,
+        permissions: user.role.permissions(),
+        credential: Credential::Token {
+            id: access_token.id,
+            scopes: access_token.scopes,
+        },
+    })
+}
+
+// The user a session or token belongs to, unless they have been suspended; their credentials stay
+// valid, so they are back in as soon as the suspension is lifted
+async fn active_user(state: &AppState, user_id: Uuid) -> Option<UserModel> {
+    let user = user_repository::get(&state.pool, user_id).await.ok()?;
+
+    if user.suspended_at.is_some() {
+        debug!("inject_user_data: user {} is suspended", user_id);
+        return None;
+    }
+
+    Some(user)
 }
 
+// Look up the live session a token belongs to, comparing the digest of its secret half in
+// constant time. Also tells whether the token has the current secret rather than the one from
+// before the last rotation, which is accepted for a short grace period.
+async fn find_session(
+    state: &AppState,
+    session_token: &str,
+    now: i64,
+) -> Option<(UserSessionModel, bool)> {
+    let (session_token_p1, session_token_p2) = session_token.split_once('_')?;
+
+    let user_session =
+        user_sessions_repository::get_by_first_part_token(&state.pool, session_token_p1.to_owned())
+            .await
+            .ok()?;
+
+    let max_lifetime = config().await.session_max_lifetime().num_seconds();
+    if user_session.expires_at <= now || user_session.created_at + max_lifetime <= now {
+        return None;
+    }
+
+    let session_token_p2_digest = digest_secret(session_token_p2);
+
+    if digests_match(
+        &session_token_p2_digest,
+        &user_session.session_token_p2_digest,
+    ) {
+        return Some((user_session, true));
+    }
+
+    let in_grace_period = user_session
+        .rotated_at
+        .is_some_and(|rotated_at| now - rotated_at < ROTATION_GRACE_SECS);
+
+    match &user_session.previous_session_token_p2_digest {
+        Some(previous) if in_grace_period && digests_match(&session_token_p2_digest, previous) => {
+            Some((user_session, false))
+        }
+        _ => None,
+    }
+}
+
+fn digests_match(digest_cookie: &str, digest_db: &str) -> bool {
+    match (
+        digest_cookie.as_bytes().try_into(),
+        digest_db.as_bytes().try_into(),
+    ) {
+        (Ok(cookie), Ok(db)) => constant_time_eq::constant_time_eq_n::<64>(cookie, db),
+        _ => false,
+    }
+}
+
+// Sliding expiry: once a session is past half of its idle timeout it is extended, though never
+// beyond its maximum lifetime, and its secret is rotated. Returns the cookie with the new secret.
+async fn slide_expiry(
+    state: &AppState,
+    user_session: &UserSessionModel,
+    now: i64,
+) -> Option<String> {
+    let config = config().await;
+    let idle_timeout = config.session_idle_timeout().num_seconds();
+    let deadline = user_session.created_at + config.session_max_lifetime().num_seconds();
+
+    let expires_at = (now + idle_timeout).min(deadline);
+    if user_session.expires_at - now > idle_timeout / 2 || expires_at <= user_session.expires_at {
+        return None;
+    }
+
+    let session_token_p2 = Uuid::new_v4().to_string();
+    let rotated = user_sessions_repository::rotate(
+        &state.pool,
+        user_session.id,
+        user_session.session_token_p2_digest.clone(),
+        digest_secret(&session_token_p2),
+        expires_at,
+        now,
+    )
+    .await;
+
+    match rotated {
+        Ok(true) => Some(session_cookie(
+            &user_session.session_token_p1,
+            &session_token_p2,
+            expires_at - now,
+        )),
+        // A concurrent request rotated the session first, its response carries the cookie
+        Ok(false) => None,
+        Err(err) => {
+            warn!("inject_user_data: failed to rotate session: {}", err);
+            None
+        }
+    }
+}
+
+// Let through requests with a user, checking that tokens have the scope for the method. Browsers
+// without one are sent to log in, anyone else gets a 401 they can act on.
 pub async fn check_auth(
     request: Request<Body>,
     next: Next,
 ) -> Result<impl IntoResponse, AuthError> {
-    if request
+    let user_data = request
         .extensions()
         .get::<Option<UserData>>()
-        .ok_or("check_auth: extensions have no UserData")?
-        .is_some()
-    {
-        Ok(next.run(request).await)
-    } else {
-        let login_url = "/api/auth/login?return_url=".to_owned() + &*request.uri().to_string();
-        Ok(Redirect::to(login_url.as_str()).into_response())
+        .ok_or("check_auth: extensions have no UserData")?;
+
+    match user_data {
+        Some(UserData {
+            credential: Credential::Token { id, scopes },
+            ..
+        }) if !scopes.iter().any(|scope| scope.allows(request.method())) => {
+            debug!(
+                "check_auth: token {} has no scope for {} {}",
+                id,
+                request.method(),
+                request.uri()
+            );
+            Err(AuthError::InsufficientScope)
+        }
+        Some(_) => Ok(next.run(request).await),
+        None if request.headers().contains_key(header::AUTHORIZATION)
+            || !accepts_html(request.headers()) =>
+        {
+            Err(AuthError::Unauthenticated)
+        }
+        None => {
+            // Nested routers only see the rest of the path, the way back needs all of it
+            let return_url = request
+                .extensions()
+                .get::<OriginalUri>()
+                .map(|original_uri| original_uri.0.to_string())
+                .unwrap_or_else(|| request.uri().to_string());
+            Ok(Redirect::to(login_url(&return_url).as_str()).into_response())
+        }
+    }
+}
+
+fn accepts_html(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/html"))
+}
+
+// A permission a handler can demand by taking `Authorized` with it
+pub trait RequiredPermission {
+    const PERMISSION: Permission;
+}
+
+pub struct CreatePosts;
+
+impl RequiredPermission for CreatePosts {
+    const PERMISSION: Permission = Permission::PostCreate;
+}
+
+pub struct PublishPosts;
+
+impl RequiredPermission for PublishPosts {
+    const PERMISSION: Permission = Permission::PostPublish;
+}
+
+pub struct ManageUsers;
+
+impl RequiredPermission for ManageUsers {
+    const PERMISSION: Permission = Permission::UserManage;
+}
+
+pub struct EditAnyPost;
+
+impl RequiredPermission for EditAnyPost {
+    const PERMISSION: Permission = Permission::PostEditAny;
+}
+
+pub struct DeleteAnyPost;
+
+impl RequiredPermission for DeleteAnyPost {
+    const PERMISSION: Permission = Permission::PostDeleteAny;
+}
+
+// The user making the request, as long as they have the permission `P`. Rejects with a 401 when
+// nobody is logged in and with a 403 when the user lacks the permission.
+pub struct Authorized<P>(pub UserData, pub PhantomData<P>);
+
+#[async_trait]
+impl<S, P> FromRequestParts<S> for Authorized<P>
+where
+    S: Send + Sync,
+    P: RequiredPermission,
+{
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let user_data = logged_in_user(parts)?;
+
+        if !user_data.has_permission(P::PERMISSION) {
+            debug!(
+                "Authorized: user {} with role {} lacks {}",
+                user_data.user_id,
+                user_data.role.as_str(),
+                P::PERMISSION.as_str()
+            );
+            return Err(AuthError::MissingPermission(P::PERMISSION));
+        }
+
+        Ok(Self(user_data, PhantomData))
+    }
+}
+
+// The user acting on posts: on their own ones always, on those of other authors only with the
+// permission `P`. Rejects with a 401 when nobody is logged in.
+pub struct PostAuthor<P>(pub UserData, pub PhantomData<P>);
+
+impl<P: RequiredPermission> PostAuthor<P> {
+    // The author the posts acted on have to be by, or none when any post will do
+    pub fn author_filter(&self) -> Option<Uuid> {
+        if self.0.has_permission(P::PERMISSION) {
+            None
+        } else {
+            Some(self.0.user_id)
+        }
     }
 }
+
+#[async_trait]
+impl<S, P> FromRequestParts<S> for PostAuthor<P>
+where
+    S: Send + Sync,
+    P: RequiredPermission,
+{
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self(logged_in_user(parts)?, PhantomData))
+    }
+}
+
+fn logged_in_user(parts: &Parts) -> Result<UserData, AuthError> {
+    parts
+        .extensions
+        .get::<Option<UserData>>()
+        .cloned()
+        .flatten()
+        .ok_or(AuthError::Unauthenticated)
+}
@@ -1,36 +1,73 @@
-use crate::handlers::auth::login::login;
-use crate::handlers::auth::oauth_return::oauth_return;
+use crate::config::Config;
+use crate::domain::problem::Problem;
+use crate::handlers::auth::create_token::create_token;
+use crate::handlers::auth::link_identity::link_identity;
+use crate::handlers::auth::list_identities::list_identities;
+use crate::handlers::auth::list_sessions::list_sessions;
+use crate::handlers::auth::list_tokens::list_tokens;
+use crate::handlers::auth::login::{login, login_default};
+use crate::handlers::auth::logout::logout;
+use crate::handlers::auth::oauth_return::{oauth_return, oauth_return_legacy};
 use crate::handlers::auth::profile::profile;
+use crate::handlers::auth::revocation_stub::revocation_stub;
+use crate::handlers::auth::revoke_other_sessions::revoke_other_sessions;
+use crate::handlers::auth::revoke_session::revoke_session;
+use crate::handlers::auth::revoke_token::revoke_token;
+use crate::handlers::auth::unlink_identity::unlink_identity;
 use crate::handlers::auth::UserData;
+use crate::handlers::comments::create_comment::create_comment;
+use crate::handlers::comments::delete_comment::delete_comment;
+use crate::handlers::comments::list_comments::list_comments;
+use crate::handlers::comments::update_comment::update_comment;
+use crate::handlers::posts::cancel_schedule::cancel_schedule;
 use crate::handlers::posts::create_post::create_post;
 use crate::handlers::posts::delete_post::delete_post;
+use crate::handlers::posts::diff_revisions::diff_revisions;
 use crate::handlers::posts::get_post::get_post;
+use crate::handlers::posts::get_post_by_slug::get_post_by_slug;
+use crate::handlers::posts::get_revision::get_revision;
 use crate::handlers::posts::list_posts::list_posts;
+use crate::handlers::posts::list_revisions::list_revisions;
+use crate::handlers::posts::list_trash::list_trash;
+use crate::handlers::posts::restore_post::restore_post;
+use crate::handlers::posts::restore_revision::restore_revision;
+use crate::handlers::posts::schedule_post::schedule_post;
 use crate::handlers::posts::update_post::update_post;
-use crate::middlewares::{check_auth, inject_user_data};
+use crate::handlers::tags::list_tags::list_tags;
+use crate::handlers::users::get_user::get_user;
+use crate::handlers::users::list_users::list_users;
+use crate::handlers::users::logout_user::logout_user;
+use crate::handlers::users::suspend_user::suspend_user;
+use crate::handlers::users::unsuspend_user::unsuspend_user;
+use crate::handlers::users::update_user_role::update_user_role;
+use crate::middlewares::{check_auth, inject_user_data, request_context};
 use crate::AppState;
 use axum::{
+    extract::DefaultBodyLimit,
     http::StatusCode,
     middleware,
     response::IntoResponse,
-    routing::{delete, get, patch, post},
+    routing::{delete, get, patch, post, put},
     Extension, Router,
 };
 use tracing::log::debug;
 
-pub fn app_router(state: AppState) -> Router<AppState> {
+pub fn app_router(state: AppState, config: &Config) -> Router<AppState> {
     let user_data: Option<UserData> = None;
 
     Router::new()
         .route("/", get(root))
-        .nest("/api/post", post_routes(state.clone()))
-        .nest("/api/auth", auth_routes(state.clone()))
+        .nest("/api/post", post_routes(state.clone(), config))
+        .nest("/api/tags", tag_routes(state.clone()))
+        .nest("/api/auth", auth_routes(state.clone(), config))
+        .nest("/api/admin/users", admin_user_routes(state.clone()))
         .layer(middleware::from_fn_with_state(
             state.clone(),
             inject_user_data,
         ))
-        .layer(Extension(user_data))
         .fallback(handler_404)
+        .layer(Extension(user_data))
+        .layer(middleware::from_fn(request_context))
 }
 
 // Handler for the root path "/"
@@ -41,26 +78,75 @@ async fn root() -> &'static str {
 async fn handler_404() -> impl IntoResponse {
     debug!("->> {:<12} - handler_404", "HANDLER");
 
-    (
+    Problem::new(
         StatusCode::NOT_FOUND,
+        "not_found",
         "The requested resource was not found",
     )
 }
 
-fn post_routes(state: AppState) -> Router<AppState> {
+fn post_routes(state: AppState, config: &Config) -> Router<AppState> {
     Router::new()
         .route("/", post(create_post))
-        .route("/:id", get(get_post))
         .route("/:id", patch(update_post))
         .route("/:id", delete(delete_post))
+        .route("/trash", get(list_trash))
+        .route("/:id/restore", post(restore_post))
+        .route("/:id/schedule", put(schedule_post))
+        .route("/:id/schedule", delete(cancel_schedule))
+        .route("/:id/revisions", get(list_revisions))
+        .route("/:id/revisions/diff", get(diff_revisions))
+        .route("/:id/revisions/:revision", get(get_revision))
+        .route("/:id/revisions/:revision/restore", post(restore_revision))
+        .route("/:id/comments", get(list_comments))
+        .route("/:id/comments", post(create_comment))
+        .route("/:id/comments/:comment_id", patch(update_comment))
+        .route("/:id/comments/:comment_id", delete(delete_comment))
+        .route_layer(middleware::from_fn_with_state(state.clone(), check_auth))
+        .route("/:id", get(get_post))
+        .route("/by-slug/:slug", get(get_post_by_slug))
         .route("/", get(list_posts))
+        .layer(DefaultBodyLimit::max(config.post_request_max_bytes()))
         .with_state(state)
 }
-fn auth_routes(state: AppState) -> Router<AppState> {
+fn tag_routes(state: AppState) -> Router<AppState> {
+    Router::new().route("/", get(list_tags)).with_state(state)
+}
+fn admin_user_routes(state: AppState) -> Router<AppState> {
     Router::new()
-        .route("/profile", get(profile))
+        .route("/", get(list_users))
+        .route("/:id", get(get_user))
+        .route("/:id/suspend", post(suspend_user))
+        .route("/:id/unsuspend", post(unsuspend_user))
+        .route("/:id/role", put(update_user_role))
+        .route("/:id/logout", post(logout_user))
         .route_layer(middleware::from_fn_with_state(state.clone(), check_auth))
-        .route("/login", get(login))
-        .route("/oauth_return", get(oauth_return))
         .with_state(state)
 }
+fn auth_routes(state: AppState, config: &Config) -> Router<AppState> {
+    let router = Router::new()
+        .route("/profile", get(profile))
+        .route("/sessions", get(list_sessions))
+        .route("/sessions/revoke_others", post(revoke_other_sessions))
+        .route("/sessions/:id", delete(revoke_session))
+        .route("/identities", get(list_identities))
+        .route("/identities/link/:provider", get(link_identity))
+        .route("/identities/:id", delete(unlink_identity))
+        .route("/tokens", get(list_tokens).post(create_token))
+        .route("/tokens/:id", delete(revoke_token))
+        .route_layer(middleware::from_fn_with_state(state.clone(), check_auth))
+        .route("/login", get(login_default))
+        .route("/login/:provider", get(login))
+        .route("/oauth_return", get(oauth_return_legacy))
+        .route("/oauth_return/:provider", get(oauth_return))
+        .route("/logout", post(logout));
+
+    // Local stand-in for the provider's revocation endpoint, see `revocation_stub`
+    let router = if config.revocation_stub() {
+        router.route("/revocation_stub", post(revocation_stub))
+    } else {
+        router
+    };
+
+    router.with_state(state)
+}
@@ -0,0 +1,43 @@
+use crate::config::Config;
+use crate::infra::repositories::{
+    auth_repository, personal_access_tokens_repository, user_sessions_repository,
+};
+use chrono::Utc;
+use deadpool_diesel::postgres::Pool;
+use tracing::log::{debug, error, info};
+
+// Periodically remove expired sessions and access tokens, and the OAuth state of logins that were
+// never finished
+pub async fn run(pool: Pool, config: &'static Config) {
+    let mut interval = tokio::time::interval(config.auth_janitor_interval());
+
+    loop {
+        interval.tick().await;
+        debug!("->> {:<12} - auth_janitor", "TASK");
+
+        match user_sessions_repository::delete_expired(&pool, Utc::now().timestamp()).await {
+            Ok(0) => {}
+            Ok(removed) => info!("auth_janitor: deleted {} expired sessions", removed),
+            Err(err) => error!("auth_janitor: failed to delete expired sessions: {}", err),
+        }
+
+        match personal_access_tokens_repository::delete_expired(&pool, Utc::now()).await {
+            Ok(0) => {}
+            Ok(removed) => info!("auth_janitor: deleted {} expired access tokens", removed),
+            Err(err) => error!(
+                "auth_janitor: failed to delete expired access tokens: {}",
+                err
+            ),
+        }
+
+        let created_before = Utc::now() - config.oauth_state_ttl();
+        match auth_repository::delete_expired_oauth2_records(&pool, created_before).await {
+            Ok(0) => {}
+            Ok(removed) => info!("auth_janitor: deleted {} abandoned OAuth records", removed),
+            Err(err) => error!(
+                "auth_janitor: failed to delete abandoned OAuth records: {}",
+                err
+            ),
+        }
+    }
+}
@@ -0,0 +1,3 @@
+pub mod auth_janitor;
+pub mod scheduled_publish;
+pub mod trash_purge;
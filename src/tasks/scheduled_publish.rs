@@ -0,0 +1,21 @@
+use crate::config::Config;
+use crate::infra::repositories::post_repository;
+use chrono::Utc;
+use deadpool_diesel::postgres::Pool;
+use tracing::log::{debug, error, info};
+
+// Periodically publish drafts whose scheduled publication time has come
+pub async fn run(pool: Pool, config: &'static Config) {
+    let mut interval = tokio::time::interval(config.publish_scheduler_interval());
+
+    loop {
+        interval.tick().await;
+        debug!("->> {:<12} - scheduled_publish", "TASK");
+
+        match post_repository::publish_due(&pool, Utc::now()).await {
+            Ok(0) => {}
+            Ok(published) => info!("scheduled_publish: published {} posts", published),
+            Err(err) => error!("scheduled_publish: failed to publish due posts: {}", err),
+        }
+    }
+}
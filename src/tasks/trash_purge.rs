@@ -0,0 +1,22 @@
+use crate::config::Config;
+use crate::infra::repositories::post_repository;
+use chrono::Utc;
+use deadpool_diesel::postgres::Pool;
+use tracing::log::{debug, error, info};
+
+// Periodically remove posts that have been in the trash for longer than the retention period
+pub async fn run(pool: Pool, config: &'static Config) {
+    let mut interval = tokio::time::interval(config.trash_purge_interval());
+
+    loop {
+        interval.tick().await;
+        debug!("->> {:<12} - trash_purge", "TASK");
+
+        let deleted_before = Utc::now() - config.trash_retention();
+        match post_repository::purge_trashed(&pool, deleted_before).await {
+            Ok(0) => {}
+            Ok(purged) => info!("trash_purge: permanently deleted {} posts", purged),
+            Err(err) => error!("trash_purge: failed to purge trashed posts: {}", err),
+        }
+    }
+}